@@ -0,0 +1,201 @@
+mod argument_hints;
+mod completion;
+mod docs;
+mod outline;
+mod references;
+mod rename;
+mod semantic_tokens;
+mod signature;
+
+use std::collections::HashMap;
+
+pub use argument_hints::{annotate, argument_hints, ArgumentHint};
+pub use completion::{completions, Completion, CompletionKind};
+pub use docs::{doc_pages, item_markdown};
+use itertools::Itertools;
+pub use outline::{file_outline, outline, Symbol};
+pub use references::{
+    label_position, labeled_fields, reference_at, references, Reference, ReferenceKind,
+};
+pub use rename::{file_modules, rename, FileRename, Rename, TextEdit};
+pub use semantic_tokens::{semantic_tokens, SemanticToken, TokenKind};
+pub use signature::{
+    call_at, constructor_signature, signature, Call, Signature, SignatureParameter,
+};
+
+use crate::{
+    definitions::parameter::ParameterPtr,
+    diagnostic::Diagnostic,
+    environment::{Def2, Def3, Env0, Env1, Env2, Env3, Environment, ItemId},
+    file_tree::FileNode,
+    parser::{self, create_root, ParseContext},
+    source_map::SourceMap,
+};
+
+/// The result of running a program through every stage of the compiler. The
+/// environment from each stage is kept so that tooling can look at both what
+/// the user wrote and what it was resolved to.
+pub struct Analysis {
+    pub file_tree: FileNode,
+    pub sources: SourceMap,
+    pub root: ItemId,
+    pub env0: Env0,
+    pub env1: Env1,
+    pub env2: Env2,
+    pub env3: Result<Env3, Vec<Diagnostic>>,
+    /// The item which defines each parameter.
+    parameter_definitions: HashMap<ParameterPtr, ItemId>,
+}
+
+impl Analysis {
+    pub fn new(file_tree: FileNode, sources: SourceMap) -> Result<Self, Vec<Diagnostic>> {
+        let parse_context = ParseContext::new();
+        let mut env0 = Environment::new();
+        let root = {
+            let mut file_counter = 0;
+            let root = parser::parse_tree(&file_tree, &parse_context, &mut file_counter)?;
+            create_root(&root, &parse_context, &mut env0).map_err(|err| vec![err])?
+        };
+        env0.compute_parents();
+        let env1 = env0.processed();
+        let env2 = env1.processed();
+        let env3 = env2.processed();
+        let mut parameter_definitions = HashMap::new();
+        for item in env2.all_item_ids() {
+            if let Def2::DParameter(parameter) = &env2[item] {
+                parameter_definitions
+                    .entry(parameter.get_parameter_ptr())
+                    .or_insert(item);
+            }
+        }
+        Ok(Self {
+            file_tree,
+            sources,
+            root,
+            env0,
+            env1,
+            env2,
+            env3,
+            parameter_definitions,
+        })
+    }
+
+    /// Finds the item which defines the given parameter.
+    pub fn parameter_definition(&self, parameter: &ParameterPtr) -> Option<ItemId> {
+        self.parameter_definitions.get(parameter).copied()
+    }
+
+    /// Returns the code an item was written as, with comments removed and runs
+    /// of whitespace collapsed to single spaces.
+    pub fn source_text(&self, item: ItemId) -> Option<String> {
+        if !self.env0.contains(item) {
+            return None;
+        }
+        let position = self.env0.get_position(item)?;
+        let text = self
+            .sources
+            .text(position)?
+            .lines()
+            .map(|line| line.split(parser::LINE_COMMENT).next().unwrap())
+            .join(" ");
+        Some(text.split_whitespace().join(" "))
+    }
+
+    /// Describes an item for display in an editor, preferring the code it was
+    /// written as.
+    pub fn describe(&self, item: ItemId) -> String {
+        if let Some(text) = self.source_text(item) {
+            return text;
+        }
+        let item = self.env2.dereference(item);
+        match &self.env2[item] {
+            Def2::DBuiltin(builtin) => builtin.get_builtin().name().to_owned(),
+            _ => self.env2.get_label(item).unwrap_or("?").to_owned(),
+        }
+    }
+
+    /// Returns the type of an item, if it is known.
+    pub fn type_of(&self, item: ItemId) -> Option<ItemId> {
+        let item = self.env2.dereference(item);
+        if let Def2::DParameter(parameter) = &self.env2[item] {
+            return Some(parameter.get_parameter().original_type());
+        }
+        let env3 = self.env3.as_ref().ok()?;
+        let r#type = env3.dereference(env3.get_type(item)?);
+        if self.env2.contains(r#type) {
+            Some(r#type)
+        } else {
+            None
+        }
+    }
+
+    /// Describes the type of an item, if it is known.
+    pub fn describe_type(&self, item: ItemId) -> Option<String> {
+        Some(self.describe(self.type_of(item)?))
+    }
+
+    /// Finds an item from a path of labels separated by dots, starting from
+    /// the root module, such as `folder.file.item`.
+    pub fn item_at_path(&self, path: &str) -> Option<ItemId> {
+        let mut item = self.root;
+        for label in path.split('.').filter(|label| !label.is_empty()) {
+            let env = &self.env2;
+            let fields = labeled_fields(self, env.dereference(item));
+            item = fields.into_iter().find(|(l, _)| l == label)?.1;
+        }
+        Some(item)
+    }
+
+    /// Returns the fields of the type of a value, which is what members of the
+    /// value refer to.
+    pub fn value_fields(&self, item: ItemId) -> Option<Vec<(String, ItemId)>> {
+        let env = self.env3.as_ref().ok()?;
+        let mut r#type = env.dereference(env.get_type(env.dereference(item))?);
+        loop {
+            match &env[r#type] {
+                Def3::DSubstitution(sub) => r#type = env.dereference(sub.base()),
+                Def3::DCompoundType(compound) => {
+                    // Only user types have fields. Builtin types like `Nat`
+                    // have none.
+                    let r#type = compound.get_single_type()?;
+                    if !r#type.is_constructable_type() {
+                        return None;
+                    }
+                    return Some(r#type.get_constructor_parameters().to_vec());
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns an item in which an identifier written at the given location
+    /// would be looked up. This works even when the analysis is out of date,
+    /// as long as the code before the location has not changed.
+    pub fn scope_at(&self, file_index: usize, offset: usize) -> ItemId {
+        let items = self
+            .env0
+            .all_item_ids()
+            .filter_map(|item| Some((item, self.env0.get_position(item)?)))
+            .filter(|(_, position)| position.file_index() == file_index)
+            .map(|(item, position)| (item, position.range()))
+            .collect_vec();
+        let containing = items
+            .iter()
+            .filter(|(_, range)| range.start <= offset && offset <= range.end)
+            .min_by_key(|(_, range)| range.len());
+        if let Some(&(item, _)) = containing {
+            return item;
+        }
+        // Otherwise, use whatever contains the last thing written before the
+        // location.
+        items
+            .iter()
+            .filter(|(_, range)| range.end <= offset)
+            .max_by_key(|(_, range)| (range.end, range.len()))
+            .and_then(|&(item, _)| self.env0.parent(item))
+            .unwrap_or(self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests;
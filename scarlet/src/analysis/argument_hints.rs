@@ -0,0 +1,74 @@
+use itertools::Itertools;
+
+use super::Analysis;
+use crate::{
+    definitions::{parameter::ParameterPtr, substitution::PartiallyResolvedTarget},
+    diagnostic::Position,
+    environment::{Def2, ItemId},
+    file_tree::FileNode,
+};
+
+/// Describes which parameter a positional argument was assigned to.
+#[derive(Clone, Debug)]
+pub struct ArgumentHint {
+    pub substitution: ItemId,
+    pub argument: ItemId,
+    pub position: Position,
+    pub parameter: ParameterPtr,
+    pub parameter_definition: Option<ItemId>,
+    pub parameter_name: Option<String>,
+}
+
+pub fn argument_hints(analysis: &Analysis) -> Vec<ArgumentHint> {
+    let env = &analysis.env2;
+    let mut hints = Vec::new();
+    for item in env.all_item_ids() {
+        let Def2::DPartiallyResolvedSubstitution(sub) = &env[item] else { continue };
+        let Ok(assignment) = sub.assign_arguments(env) else { continue };
+        let positional = sub
+            .substitutions()
+            .iter()
+            .filter(|(target, _)| target == &PartiallyResolvedTarget::Positional)
+            .map(|(_, argument)| *argument)
+            .collect_vec();
+        for (parameter, argument) in &assignment.explicit {
+            if !positional.contains(argument) {
+                continue;
+            }
+            let Some(position) = env.get_position(*argument) else { continue };
+            let parameter_definition = analysis.parameter_definition(parameter);
+            let parameter_name = parameter_definition
+                .and_then(|definition| env.get_label(definition))
+                .map(String::from);
+            hints.push(ArgumentHint {
+                substitution: item,
+                argument: *argument,
+                position,
+                parameter: parameter.clone(),
+                parameter_definition,
+                parameter_name,
+            });
+        }
+    }
+    hints.sort_by_key(|hint| (hint.position.file_index(), hint.position.range().start));
+    hints
+}
+
+/// Returns the path and content of every file in the tree, with each
+/// positional argument rewritten as a named argument.
+pub fn annotate(files: &FileNode, hints: &[ArgumentHint]) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    for (index, (path, content)) in files.files().into_iter().enumerate() {
+        let mut annotated = content.to_owned();
+        for hint in hints.iter().rev() {
+            if hint.position.file_index() != index + 1 {
+                continue;
+            }
+            if let Some(name) = &hint.parameter_name {
+                annotated.insert_str(hint.position.range().start, &format!("{} IS ", name));
+            }
+        }
+        result.push((path, annotated));
+    }
+    result
+}
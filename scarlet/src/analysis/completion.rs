@@ -0,0 +1,209 @@
+use itertools::Itertools;
+
+use super::{call_at, signature::resolve_item, Analysis};
+use crate::{
+    environment::{Def0, Def2, ItemId},
+    parser::{self, ParseContext},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompletionKind {
+    Module,
+    Type,
+    Parameter,
+    Value,
+    Constructor,
+    /// The name of a parameter, to be used as the target of an argument in a
+    /// substitution.
+    NamedArgument,
+}
+
+/// Something which could be written at the cursor.
+#[derive(Clone, Debug)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub r#type: Option<String>,
+    pub item: Option<ItemId>,
+    pub insert_text: String,
+}
+
+fn kind_of(analysis: &Analysis, item: ItemId) -> CompletionKind {
+    match &analysis.env2[analysis.env2.dereference(item)] {
+        Def2::DStructLiteral(..) => CompletionKind::Module,
+        Def2::DCompoundType(..) => CompletionKind::Type,
+        Def2::DParameter(..) => CompletionKind::Parameter,
+        _ => CompletionKind::Value,
+    }
+}
+
+fn item_completion(analysis: &Analysis, label: &str, item: ItemId) -> Completion {
+    Completion {
+        label: label.to_owned(),
+        kind: kind_of(analysis, item),
+        r#type: analysis.describe_type(item),
+        item: Some(item),
+        insert_text: label.to_owned(),
+    }
+}
+
+fn member_completions(analysis: &Analysis, base: ItemId) -> Vec<Completion> {
+    let env = &analysis.env2;
+    match &env[env.dereference(base)] {
+        Def2::DStructLiteral(module) => module
+            .fields()
+            .iter()
+            .filter(|(label, _)| !label.is_empty())
+            .map(|(label, field)| item_completion(analysis, label, *field))
+            .collect(),
+        Def2::DCompoundType(..) => vec![Completion {
+            label: "new".to_owned(),
+            kind: CompletionKind::Constructor,
+            r#type: analysis.source_text(base),
+            item: None,
+            insert_text: "new".to_owned(),
+        }],
+        _ => analysis
+            .value_fields(base)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(label, field)| Completion {
+                label: label.clone(),
+                kind: CompletionKind::Value,
+                r#type: analysis.describe_type(field),
+                item: None,
+                insert_text: label,
+            })
+            .collect(),
+    }
+}
+
+/// Whether an identifier with the given label would refer to something
+/// other than what it does in an outer scope because of the given candidate.
+fn is_shadowed_by(label: &str, candidate: &Completion) -> bool {
+    candidate.label == label && candidate.kind != CompletionKind::NamedArgument
+}
+
+/// Lists the identifiers visible from the given item, innermost first.
+fn visible_identifiers(analysis: &Analysis, context: ItemId) -> Vec<Completion> {
+    let mut result: Vec<Completion> = Vec::new();
+    let mut scope = Some(context);
+    while let Some(item) = scope {
+        if let Def0::DStructLiteral(r#struct) = &analysis.env0[item] {
+            for (label, field) in r#struct.fields() {
+                if !label.is_empty() && !result.iter().any(|c| &c.label == label) {
+                    result.push(item_completion(analysis, label, *field));
+                }
+            }
+        }
+        scope = analysis.env0.parent(item);
+    }
+    result
+}
+
+/// Lists the named arguments and identifiers which could be written at the
+/// end of the given code.
+fn identifier_completions(
+    analysis: &Analysis,
+    file_index: usize,
+    code: &str,
+    parse_context: &ParseContext,
+) -> Vec<Completion> {
+    let mut candidates = Vec::new();
+    if let Some(call) = call_at(analysis, file_index, code) {
+        for (index, parameter) in call.signature.parameters.iter().enumerate() {
+            let Some(name) = &parameter.name else { continue };
+            if call.assigned.contains(&index) {
+                continue;
+            }
+            candidates.push(Completion {
+                label: name.clone(),
+                kind: CompletionKind::NamedArgument,
+                r#type: Some(parameter.r#type.clone()),
+                item: parameter.definition,
+                insert_text: format!("{} IS ", name),
+            });
+        }
+    }
+    // Fields written since the program was last analyzed are not known to the
+    // analysis, so they are found by parsing the code instead. Where the
+    // analysis does know about them, its information is used.
+    let context = analysis.scope_at(file_index, code.len());
+    let mut identifiers = visible_identifiers(analysis, context);
+    for label in parser::open_labels(code, parse_context, file_index) {
+        if candidates.iter().any(|c| is_shadowed_by(label, c)) {
+            continue;
+        }
+        if let Some(index) = identifiers.iter().position(|c| c.label == label) {
+            candidates.push(identifiers.remove(index));
+        } else {
+            candidates.push(Completion {
+                label: label.to_owned(),
+                kind: CompletionKind::Value,
+                r#type: None,
+                item: None,
+                insert_text: label.to_owned(),
+            });
+        }
+    }
+    for candidate in identifiers {
+        if !candidates
+            .iter()
+            .any(|c| is_shadowed_by(&candidate.label, c))
+        {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Returns everything which could be written at the end of the given code,
+/// which should be everything in a file up to the cursor. Candidates are
+/// ranked from most to least relevant. Like [`call_at`], this works with an
+/// out of date analysis.
+pub fn completions(
+    analysis: &Analysis,
+    file_index: usize,
+    code_before_cursor: &str,
+) -> Vec<Completion> {
+    let prefix_start = code_before_cursor
+        .trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        .len();
+    let (code, prefix) = code_before_cursor.split_at(prefix_start);
+    let parse_context = ParseContext::new();
+    let candidates = match parser::open_member_access(code, &parse_context, file_index) {
+        Some(base) => {
+            let context = analysis.scope_at(file_index, base.position.range().start);
+            match resolve_item(analysis, context, &base) {
+                Some(base) => member_completions(analysis, base),
+                None => vec![],
+            }
+        }
+        None => identifier_completions(analysis, file_index, code, &parse_context),
+    };
+
+    // Candidates which start with what has been typed so far come first,
+    // then ones which only do so when ignoring case. Otherwise, the order
+    // they were found in is kept, which puts named arguments and then
+    // identifiers from inner scopes first.
+    let lowercase_prefix = prefix.to_lowercase();
+    candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            if candidate.label.starts_with(prefix) {
+                Some((0, index, candidate))
+            } else if candidate
+                .label
+                .to_lowercase()
+                .starts_with(&lowercase_prefix)
+            {
+                Some((1, index, candidate))
+            } else {
+                None
+            }
+        })
+        .sorted_by_key(|&(quality, index, _)| (quality, index))
+        .map(|(_, _, candidate)| candidate)
+        .collect()
+}
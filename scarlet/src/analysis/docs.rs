@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::{
+    file_modules, file_outline, labeled_fields, semantic_tokens::kind_of, signature, Analysis,
+    Symbol, TokenKind,
+};
+use crate::environment::{Def0, ItemId};
+
+/// Where the documentation of each labeled item is, for linking to it from
+/// other pages.
+struct Links {
+    /// The page and anchor of each item, keyed by what the item refers to.
+    anchors: HashMap<ItemId, (String, String)>,
+}
+
+impl Links {
+    fn url(&self, analysis: &Analysis, from_page: &str, item: ItemId) -> Option<String> {
+        let (page, anchor) = self.anchors.get(&analysis.env2.dereference(item))?;
+        let up = "../".repeat(from_page.matches('/').count());
+        Some(format!("{}{}#{}", up, page, anchor))
+    }
+}
+
+/// Formats an item as code, linking to its documentation if there is any.
+fn code(analysis: &Analysis, text: &str, item: ItemId, links: Option<(&Links, &str)>) -> String {
+    let url = links.and_then(|(links, page)| links.url(analysis, page, item));
+    match url {
+        Some(url) => format!("[`{}`]({})", text, url),
+        None => format!("`{}`", text),
+    }
+}
+
+fn render_item(
+    analysis: &Analysis,
+    name: &str,
+    item: ItemId,
+    links: Option<(&Links, &str)>,
+) -> String {
+    let kind = kind_of(analysis, item);
+    let mut markdown = format!(
+        "```scarlet\n{} IS {}\n```\n\n",
+        name,
+        analysis.describe(item)
+    );
+    if let Some(doc) = analysis.env0.get_doc(item) {
+        markdown.push_str(&format!("{}\n\n", doc));
+    }
+    if let Some(r#type) = analysis.type_of(item) {
+        let text = match analysis.env2.get_label(r#type) {
+            Some(label) => label.to_owned(),
+            None => analysis.describe(r#type),
+        };
+        let r#type = code(analysis, &text, r#type, links);
+        markdown.push_str(&format!("**Type:** {}\n\n", r#type));
+    }
+    let parameters = signature(analysis, item).parameters;
+    if kind != TokenKind::Parameter && !parameters.is_empty() {
+        markdown.push_str("**Parameters:**\n\n");
+        for parameter in parameters {
+            let code = match (&parameter.name, parameter.definition) {
+                (Some(name), Some(definition)) => {
+                    let text = format!("{} IS {}", name, parameter.code);
+                    code(analysis, &text, definition, links)
+                }
+                _ => format!("`{}`", parameter.code),
+            };
+            markdown.push_str(&format!("- {}\n", code));
+        }
+        markdown.push_str("\n");
+    }
+    let target = analysis.env2.dereference(item);
+    let is_type =
+        analysis.env0.contains(target) && matches!(analysis.env0[target], Def0::DCompoundType(..));
+    let fields = if is_type {
+        labeled_fields(analysis, target)
+    } else {
+        vec![]
+    };
+    if !fields.is_empty() {
+        markdown.push_str("**Fields:**\n\n");
+        for (label, field) in fields {
+            let text = format!("{} IS {}", label, analysis.describe(field));
+            markdown.push_str(&format!("- {}", code(analysis, &text, field, None)));
+            if let Some(doc) = analysis.env0.get_doc(field) {
+                markdown.push_str(&format!(": {}", doc.split_whitespace().join(" ")));
+            }
+            markdown.push_str("\n");
+        }
+        markdown.push_str("\n");
+    }
+    markdown
+}
+
+/// Describes an item in Markdown, including the documentation written before
+/// its definition.
+pub fn item_markdown(analysis: &Analysis, name: &str, item: ItemId) -> String {
+    render_item(analysis, name, item, None)
+        .trim_end()
+        .to_owned()
+}
+
+fn page_path(file_path: &str) -> String {
+    if file_path.is_empty() {
+        "index.md".to_owned()
+    } else {
+        format!("{}.md", &file_path[1..])
+    }
+}
+
+fn collect_anchors(
+    analysis: &Analysis,
+    symbols: &[Symbol],
+    page: &str,
+    prefix: &str,
+    into: &mut HashMap<ItemId, (String, String)>,
+) {
+    for symbol in symbols {
+        let anchor = format!("{}{}", prefix, symbol.name);
+        let target = analysis.env2.dereference(symbol.item);
+        into.entry(target)
+            .or_insert_with(|| (page.to_owned(), anchor.clone()));
+        let prefix = format!("{}.", anchor);
+        collect_anchors(analysis, &symbol.children, page, &prefix, into);
+    }
+}
+
+fn render_symbols(
+    analysis: &Analysis,
+    symbols: &[Symbol],
+    links: &Links,
+    page: &str,
+    prefix: &str,
+    depth: usize,
+    into: &mut String,
+) {
+    for symbol in symbols {
+        let anchor = format!("{}{}", prefix, symbol.name);
+        into.push_str(&format!("<a id=\"{}\"></a>\n\n", anchor));
+        let heading = "#".repeat(depth.min(6));
+        into.push_str(&format!("{} `{}`\n\n", heading, anchor));
+        into.push_str(&format!("*{}*\n\n", symbol.kind.name()));
+        let item_links = Some((links, page));
+        into.push_str(&render_item(analysis, &symbol.name, symbol.item, item_links));
+        let prefix = format!("{}.", anchor);
+        let children = &symbol.children;
+        render_symbols(analysis, children, links, page, &prefix, depth + 1, into);
+    }
+}
+
+/// Renders documentation for every file in the program as Markdown, with
+/// one page for each file. Returns the path and content of each page.
+pub fn doc_pages(analysis: &Analysis) -> Vec<(String, String)> {
+    let modules = file_modules(analysis);
+    let outlines: Vec<_> = (0..modules.len())
+        .map(|index| file_outline(analysis, index + 1))
+        .collect();
+    let mut anchors = HashMap::new();
+    for ((path, _), outline) in modules.iter().zip(&outlines) {
+        collect_anchors(analysis, outline, &page_path(path), "", &mut anchors);
+    }
+    let links = Links { anchors };
+
+    let mut pages = Vec::new();
+    for ((path, _), outline) in modules.iter().zip(&outlines) {
+        let page = page_path(path);
+        let title = if path.is_empty() {
+            "Index".to_owned()
+        } else {
+            path[1..].replace('/', ".")
+        };
+        let mut content = format!("# {}\n\n", title);
+        let children: Vec<_> = modules
+            .iter()
+            .filter(|(child, _)| child.rfind('/').map(|end| &child[..end]) == Some(path))
+            .collect();
+        if !children.is_empty() {
+            content.push_str("**Files:**\n\n");
+            for (child, _) in children {
+                let name = &child[child.rfind('/').unwrap() + 1..];
+                let up = "../".repeat(page.matches('/').count());
+                content.push_str(&format!("- [{}]({}{})\n", name, up, page_path(child)));
+            }
+            content.push_str("\n");
+        }
+        render_symbols(analysis, outline, &links, &page, "", 2, &mut content);
+        pages.push((page, content.trim_end().to_owned() + "\n"));
+    }
+    pages
+}
@@ -0,0 +1,67 @@
+use super::{
+    file_modules, label_position, labeled_fields, semantic_tokens::kind_of, Analysis, TokenKind,
+};
+use crate::{
+    diagnostic::Position,
+    environment::{Def0, ItemId},
+};
+
+/// A labeled item in a structure, along with the labeled items inside it.
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub item: ItemId,
+    pub kind: TokenKind,
+    /// The position of the whole definition, as in `label IS value`. This is
+    /// `None` for modules created from files.
+    pub position: Option<Position>,
+    /// The position of just the label.
+    pub label_position: Option<Position>,
+    pub children: Vec<Symbol>,
+}
+
+fn symbols_in(analysis: &Analysis, item: ItemId) -> Vec<Symbol> {
+    let Def0::DStructLiteral(..) = &analysis.env0[item] else { return vec![] };
+    let mut symbols = Vec::new();
+    for (name, field) in labeled_fields(analysis, item) {
+        let label_position = label_position(analysis, field, &name);
+        let position = analysis
+            .env0
+            .get_position(field)
+            .filter(|position| position.file_index() != 0)
+            .map(|mut position| {
+                if let Some(label_position) = label_position {
+                    position.extend(label_position);
+                }
+                position
+            });
+        symbols.push(Symbol {
+            name,
+            item: field,
+            kind: kind_of(analysis, field),
+            position,
+            label_position,
+            children: symbols_in(analysis, field),
+        });
+    }
+    symbols
+}
+
+/// Returns every labeled item in the program, nested the same way as the
+/// structures, files and folders they are defined in.
+pub fn outline(analysis: &Analysis) -> Vec<Symbol> {
+    symbols_in(analysis, analysis.root)
+}
+
+/// Returns the labeled items defined in the given file.
+pub fn file_outline(analysis: &Analysis, file_index: usize) -> Vec<Symbol> {
+    let modules = file_modules(analysis);
+    let module = file_index
+        .checked_sub(1)
+        .and_then(|index| modules.get(index));
+    let Some(&(_, module)) = module else { return vec![] };
+    symbols_in(analysis, module)
+        .into_iter()
+        .filter(|symbol| symbol.position.map(|p| p.file_index()) == Some(file_index))
+        .collect()
+}
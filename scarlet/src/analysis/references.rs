@@ -0,0 +1,148 @@
+use super::Analysis;
+use crate::{
+    definitions::substitution::UnresolvedTarget,
+    diagnostic::Position,
+    environment::{Def0, Def2, ItemId},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReferenceKind {
+    /// The label of a field, as in `label IS value`.
+    Definition,
+    Identifier,
+    /// The name of a member, as in `base.member`.
+    Member,
+    /// The target of an argument, as in `base(target IS value)`.
+    NamedArgument,
+}
+
+/// A place in the code where a name is written which refers to an item.
+#[derive(Clone, Debug)]
+pub struct Reference {
+    /// The position of just the name.
+    pub position: Position,
+    pub name: String,
+    pub target: ItemId,
+    pub kind: ReferenceKind,
+    /// The item the name is looked up from, for names which are looked up in
+    /// enclosing structures.
+    pub scope: Option<ItemId>,
+}
+
+/// Finds where the label of a field is written, given the value it labels.
+pub fn label_position(analysis: &Analysis, value: ItemId, label: &str) -> Option<Position> {
+    let position = analysis.env0.get_position(value)?;
+    let content = &analysis.sources.file(position.file_index())?.content;
+    let before = content[..position.range().start].trim_end();
+    let before = before.strip_suffix("IS")?.trim_end();
+    let start = before.len().checked_sub(label.len())?;
+    let is_part_of_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if !before.ends_with(label) || before[..start].ends_with(is_part_of_identifier) {
+        return None;
+    }
+    Some(Position::new(position.file_index(), start..before.len()))
+}
+
+/// Returns the labeled fields of a structure or of the constructor of a
+/// type.
+pub fn labeled_fields(analysis: &Analysis, item: ItemId) -> Vec<(String, ItemId)> {
+    let fields = match &analysis.env0[item] {
+        Def0::DStructLiteral(r#struct) => r#struct.fields().to_vec(),
+        Def0::DCompoundType(r#type) => r#type
+            .get_component_types()
+            .values()
+            .filter(|r#type| r#type.is_constructable_type())
+            .flat_map(|r#type| r#type.get_constructor_parameters().to_vec())
+            .collect(),
+        _ => vec![],
+    };
+    fields
+        .into_iter()
+        .filter(|(label, _)| !label.is_empty())
+        .collect()
+}
+
+fn member_target(analysis: &Analysis, access: ItemId, base: ItemId, name: &str) -> Option<ItemId> {
+    match &analysis.env2[access] {
+        Def2::DOther(other) => Some(other.0),
+        Def2::DUnresolvedMemberAccess(..) => analysis
+            .value_fields(base)?
+            .into_iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, field)| field),
+        _ => None,
+    }
+}
+
+/// Lists every place a name is written and what it refers to.
+pub fn references(analysis: &Analysis) -> Vec<Reference> {
+    let env = &analysis.env0;
+    let mut references = Vec::new();
+    for item in env.all_item_ids() {
+        for (label, field) in labeled_fields(analysis, item) {
+            let Some(position) = label_position(analysis, field, &label) else { continue };
+            references.push(Reference {
+                position,
+                name: label,
+                target: field,
+                kind: ReferenceKind::Definition,
+                scope: None,
+            });
+        }
+        let Some(position) = env.get_position(item) else { continue };
+        match &env[item] {
+            Def0::DIdentifier(ident) => {
+                let Some(scope) = env.parent(item) else { continue };
+                let name = ident.identifier();
+                let Some(target) = env.lookup_identifier(item, name) else { continue };
+                references.push(Reference {
+                    position,
+                    name: name.to_owned(),
+                    target,
+                    kind: ReferenceKind::Identifier,
+                    scope: Some(scope),
+                });
+            }
+            Def0::DUnresolvedMemberAccess(access) => {
+                let name = access.member_name();
+                let base = access.base();
+                let Some(target) = member_target(analysis, item, base, name) else { continue };
+                let end = position.range().end;
+                references.push(Reference {
+                    position: Position::new(position.file_index(), end - name.len()..end),
+                    name: name.to_owned(),
+                    target,
+                    kind: ReferenceKind::Member,
+                    scope: None,
+                });
+            }
+            Def0::DUnresolvedSubstitution(sub) => {
+                for (target, value) in sub.substitutions() {
+                    let UnresolvedTarget::Named(name) = target else { continue };
+                    let Some(position) = label_position(analysis, *value, name) else { continue };
+                    let Some(target) = env.lookup_identifier(item, name) else { continue };
+                    references.push(Reference {
+                        position,
+                        name: name.clone(),
+                        target,
+                        kind: ReferenceKind::NamedArgument,
+                        scope: Some(item),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    references.sort_by_key(|r| (r.position.file_index(), r.position.range().start));
+    references
+}
+
+/// Returns the reference whose name contains the given location.
+pub fn reference_at(analysis: &Analysis, file_index: usize, offset: usize) -> Option<Reference> {
+    references(analysis).into_iter().find(|reference| {
+        let range = reference.position.range();
+        reference.position.file_index() == file_index
+            && range.start <= offset
+            && offset <= range.end
+    })
+}
@@ -0,0 +1,201 @@
+use super::{labeled_fields, references, Analysis, ReferenceKind};
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Position},
+    environment::{Def0, ItemId},
+    file_tree::FileNode,
+};
+
+/// Replaces the code at a position.
+#[derive(Clone, Debug)]
+pub struct TextEdit {
+    pub position: Position,
+    pub new_text: String,
+}
+
+/// Renames a file, along with the folder next to it if there is one. Paths
+/// are written like the ones returned by [`FileNode::files`].
+#[derive(Clone, Debug)]
+pub struct FileRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Everything that needs to change to rename an item.
+#[derive(Clone, Debug, Default)]
+pub struct Rename {
+    pub edits: Vec<TextEdit>,
+    pub file_renames: Vec<FileRename>,
+}
+
+fn collect_file_modules(
+    analysis: &Analysis,
+    node: &FileNode,
+    path: String,
+    item: ItemId,
+    into: &mut Vec<(String, ItemId)>,
+) {
+    into.push((path.clone(), item));
+    let Def0::DStructLiteral(module) = &analysis.env0[item] else { return };
+    // The modules of the files in a folder come after everything written in
+    // the file next to the folder.
+    let fields = module.fields();
+    let children = &fields[fields.len() - node.children.len()..];
+    for ((name, child), &(_, field)) in node.children.iter().zip(children) {
+        let path = format!("{}/{}", path, name);
+        collect_file_modules(analysis, child, path, field, into);
+    }
+}
+
+/// Returns the path of every file along with the module it defines.
+pub fn file_modules(analysis: &Analysis) -> Vec<(String, ItemId)> {
+    let mut modules = Vec::new();
+    let root = analysis.root;
+    let tree = &analysis.file_tree;
+    collect_file_modules(analysis, tree, String::new(), root, &mut modules);
+    modules
+}
+
+/// Returns the structure or type which gives the item a label, along with the
+/// label.
+fn definition_of(analysis: &Analysis, item: ItemId) -> Option<(ItemId, String)> {
+    let parent = analysis.env0.parent(item)?;
+    labeled_fields(analysis, parent)
+        .into_iter()
+        .find(|&(_, field)| field == item)
+        .map(|(label, _)| (parent, label))
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.contains(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// Looks up a name from the given scope, returning the first of the given
+/// structures to define it or the field of any other structure which does.
+fn first_definer(
+    analysis: &Analysis,
+    scope: ItemId,
+    definers: &[ItemId],
+    name: &str,
+) -> Option<ItemId> {
+    let mut scope = Some(scope);
+    while let Some(item) = scope {
+        if definers.contains(&item) {
+            return Some(item);
+        }
+        if let Def0::DStructLiteral(r#struct) = &analysis.env0[item] {
+            if let Some(field) = r#struct.get_field(name) {
+                return Some(field);
+            }
+        }
+        scope = analysis.env0.parent(item);
+    }
+    None
+}
+
+/// Works out how to give an item a new name, updating every reference to it
+/// across all files. Fails if a reference would end up referring to something
+/// else, or if something else would end up being referred to by one of its
+/// references.
+pub fn rename(analysis: &Analysis, target: ItemId, new_name: &str) -> Result<Rename, Diagnostic> {
+    if !is_valid_identifier(new_name) {
+        return Err(Diagnostic::new()
+            .with_code(ErrorCode::InvalidNewName)
+            .with_text_error(format!("\"{}\" is not a valid identifier.", new_name)));
+    }
+    let Some((definer, old_name)) = definition_of(analysis, target) else {
+        return Err(Diagnostic::new()
+            .with_code(ErrorCode::RenameUnlabeled)
+            .with_text_error("Only items which are labeled with IS can be renamed:".to_owned())
+            .with_item_error(target, &analysis.env0));
+    };
+    if old_name == new_name {
+        return Ok(Rename::default());
+    }
+    let taken = labeled_fields(analysis, definer)
+        .into_iter()
+        .find(|(label, _)| label == new_name);
+    if let Some((_, other)) = taken {
+        return Err(Diagnostic::new()
+            .with_code(ErrorCode::RenameNameTaken)
+            .with_text_error(format!(
+                "Cannot rename {} to {} because that name is already used here:",
+                old_name, new_name
+            ))
+            .with_item_error(other, &analysis.env0));
+    }
+
+    let references = references(analysis);
+    // Members of values can only be resolved once types are known, which is
+    // not the case when the program has errors.
+    for item in analysis.env0.all_item_ids() {
+        let Def0::DUnresolvedMemberAccess(access) = &analysis.env0[item] else { continue };
+        let Some(position) = analysis.env0.get_position(item) else { continue };
+        let is_resolved = references.iter().any(|reference| {
+            reference.kind == ReferenceKind::Member
+                && reference.position.file_index() == position.file_index()
+                && reference.position.range().end == position.range().end
+        });
+        if access.member_name() == old_name && !is_resolved {
+            return Err(Diagnostic::new()
+                .with_code(ErrorCode::RenameUnresolvedMember)
+                .with_text_error(format!(
+                    "Cannot rename {} because it is unclear what this member refers to:",
+                    old_name
+                ))
+                .with_item_error(item, &analysis.env0)
+                .with_text_info("Errors in the program may need to be fixed first.".to_owned()));
+        }
+    }
+
+    let mut rename = Rename::default();
+    for reference in &references {
+        if reference.target == target {
+            if let Some(scope) = reference.scope {
+                let found = first_definer(analysis, scope, &[definer], new_name);
+                if let Some(other) = found.filter(|&found| found != definer) {
+                    return Err(Diagnostic::new()
+                        .with_code(ErrorCode::RenameChangesReference)
+                        .with_text_error(format!(
+                            "Cannot rename {} to {} because this reference would refer to \
+                             something else:",
+                            old_name, new_name
+                        ))
+                        .with_source_code_block_error(reference.position)
+                        .with_text_info("It would refer to this instead:".to_owned())
+                        .with_item_info(other, &analysis.env0));
+                }
+            }
+            rename.edits.push(TextEdit {
+                position: reference.position,
+                new_text: new_name.to_owned(),
+            });
+        } else if reference.name == new_name {
+            let Some(scope) = reference.scope else { continue };
+            let other = definition_of(analysis, reference.target);
+            let Some((other_definer, _)) = other else { continue };
+            let found = first_definer(analysis, scope, &[definer, other_definer], new_name);
+            if found == Some(definer) {
+                return Err(Diagnostic::new()
+                    .with_code(ErrorCode::RenameChangesReference)
+                    .with_text_error(format!(
+                        "Cannot rename {} to {} because this reference to something else would \
+                         refer to it instead:",
+                        old_name, new_name
+                    ))
+                    .with_source_code_block_error(reference.position));
+            }
+        }
+    }
+
+    let modules = file_modules(analysis);
+    if let Some((path, _)) = modules.iter().find(|&&(_, module)| module == target) {
+        let parent = &path[..path.rfind('/').unwrap_or(0)];
+        rename.file_renames.push(FileRename {
+            from: path.clone(),
+            to: format!("{}/{}", parent, new_name),
+        });
+    }
+    Ok(rename)
+}
@@ -0,0 +1,108 @@
+use super::{references, Analysis, ReferenceKind};
+use crate::{
+    diagnostic::Position,
+    environment::{Def0, Def1, Def2, ItemId},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    Type,
+    Parameter,
+    Constructor,
+    Module,
+    Builtin,
+    Value,
+}
+
+impl TokenKind {
+    pub const ALL: [TokenKind; 6] = [
+        TokenKind::Type,
+        TokenKind::Parameter,
+        TokenKind::Constructor,
+        TokenKind::Module,
+        TokenKind::Builtin,
+        TokenKind::Value,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Type => "type",
+            TokenKind::Parameter => "parameter",
+            TokenKind::Constructor => "constructor",
+            TokenKind::Module => "module",
+            TokenKind::Builtin => "builtin",
+            TokenKind::Value => "value",
+        }
+    }
+}
+
+/// Says what a name in the code refers to, for highlighting it.
+#[derive(Clone, Debug)]
+pub struct SemanticToken {
+    pub position: Position,
+    pub kind: TokenKind,
+    /// Whether this is where the item is given its name.
+    pub declaration: bool,
+    /// Whether the item is used by the language itself, like `Bool`.
+    pub language_item: bool,
+}
+
+pub(super) fn kind_of(analysis: &Analysis, item: ItemId) -> TokenKind {
+    // Some builtins, like `Type`, become ordinary items once processed.
+    let env1 = &analysis.env1;
+    if let Def1::DBuiltin(..) = &env1[env1.dereference(item)] {
+        return TokenKind::Builtin;
+    }
+    match &analysis.env2[analysis.env2.dereference(item)] {
+        Def2::DCompoundType(..) => TokenKind::Type,
+        Def2::DParameter(..) => TokenKind::Parameter,
+        Def2::DConstructor(..) => TokenKind::Constructor,
+        Def2::DStructLiteral(..) => TokenKind::Module,
+        Def2::DBuiltin(..) => TokenKind::Builtin,
+        _ => {
+            // Values like `Union(True False)` are types too.
+            let Ok(env3) = &analysis.env3 else { return TokenKind::Value };
+            let r#type = env3.get_type(item).map(|r#type| env3.dereference(r#type));
+            if r#type == Some(env3.god_type()) {
+                TokenKind::Type
+            } else {
+                TokenKind::Value
+            }
+        }
+    }
+}
+
+/// Classifies every name written in the program by what it refers to.
+pub fn semantic_tokens(analysis: &Analysis) -> Vec<SemanticToken> {
+    let mut tokens = Vec::new();
+    for reference in references(analysis) {
+        let target = analysis.env2.dereference(reference.target);
+        tokens.push(SemanticToken {
+            position: reference.position,
+            kind: kind_of(analysis, target),
+            declaration: reference.kind == ReferenceKind::Definition,
+            language_item: analysis.env2.is_language_item(target),
+        });
+    }
+    // `Type.new` is not a reference to an item, since constructors are
+    // created on the spot. `Type(args).new` gives the arguments to one.
+    for item in analysis.env0.all_item_ids() {
+        let Def0::DUnresolvedMemberAccess(access) = &analysis.env0[item] else { continue };
+        let constructor = match &analysis.env2[item] {
+            Def2::DPartiallyResolvedSubstitution(sub) => sub.base(),
+            _ => item,
+        };
+        let Def2::DConstructor(..) = &analysis.env2[constructor] else { continue };
+        let Some(position) = analysis.env0.get_position(item) else { continue };
+        let end = position.range().end;
+        let start = end - access.member_name().len();
+        tokens.push(SemanticToken {
+            position: Position::new(position.file_index(), start..end),
+            kind: TokenKind::Constructor,
+            declaration: false,
+            language_item: false,
+        });
+    }
+    tokens.sort_by_key(|token| (token.position.file_index(), token.position.range().start));
+    tokens
+}
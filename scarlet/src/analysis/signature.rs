@@ -0,0 +1,239 @@
+use std::{collections::HashSet, ops::Range};
+
+use itertools::Itertools;
+
+use super::Analysis;
+use crate::{
+    definitions::{
+        parameter::ParameterPtr,
+        substitution::{type_parameters, ArgumentAssignment},
+    },
+    environment::{Def2, ItemId},
+    parser::{self, Node, OpenArgument, ParseContext},
+};
+
+/// One of the parameters in a [`Signature`].
+#[derive(Clone, Debug)]
+pub struct SignatureParameter {
+    pub parameter: ParameterPtr,
+    pub definition: Option<ItemId>,
+    pub name: Option<String>,
+    /// The code which defines the parameter, such as `ANY Bool`.
+    pub code: String,
+    pub r#type: String,
+    /// Whether this parameter is the type of another parameter, which lets it
+    /// be skipped by positional arguments and inferred instead.
+    pub inferable: bool,
+}
+
+/// The free parameters of an item, in the order positional arguments are
+/// assigned to them.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    pub parameters: Vec<SignatureParameter>,
+}
+
+impl Signature {
+    /// Formats the signature as a substitution of the given callee which
+    /// names every parameter. Also returns the range of the label taken up
+    /// by each parameter.
+    pub fn label(&self, callee: &str) -> (String, Vec<Range<usize>>) {
+        let mut label = format!("{}(", callee);
+        let mut ranges = Vec::new();
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            if index > 0 {
+                label.push_str(", ");
+            }
+            let start = label.len();
+            if let Some(name) = &parameter.name {
+                label.push_str(&format!("{} IS ", name));
+            }
+            label.push_str(&parameter.code);
+            ranges.push(start..label.len());
+        }
+        label.push(')');
+        (label, ranges)
+    }
+}
+
+fn signature_of_deps(analysis: &Analysis, deps: &HashSet<ParameterPtr>) -> Signature {
+    let env = &analysis.env2;
+    let mut deps = deps.iter().cloned().collect_vec();
+    deps.sort_by_key(|dep| dep.order());
+    let type_parameters = type_parameters(env, &deps);
+    let parameters = deps
+        .iter()
+        .map(|parameter| {
+            let definition = analysis.parameter_definition(parameter);
+            let name = definition
+                .and_then(|definition| env.get_label(definition))
+                .map(String::from);
+            let r#type = analysis.describe(parameter.original_type());
+            let code = definition
+                .and_then(|definition| analysis.source_text(definition))
+                .unwrap_or_else(|| format!("ANY {}", r#type));
+            let inferable = type_parameters.contains(parameter);
+            SignatureParameter {
+                parameter: parameter.clone(),
+                definition,
+                name,
+                code,
+                r#type,
+                inferable,
+            }
+        })
+        .collect();
+    Signature { parameters }
+}
+
+/// Returns the parameters which can be substituted in the given item.
+pub fn signature(analysis: &Analysis, item: ItemId) -> Signature {
+    signature_of_deps(analysis, analysis.env2.get_deps(item))
+}
+
+/// Returns the parameters of the constructor of the given type, which is
+/// what `Type.new` refers to.
+pub fn constructor_signature(analysis: &Analysis, r#type: ItemId) -> Option<Signature> {
+    let env = &analysis.env2;
+    let Def2::DCompoundType(r#type) = &env[env.dereference(r#type)] else { return None };
+    // Builtin types like `Nat` have no constructor.
+    let components = r#type
+        .get_component_types()
+        .values()
+        .filter(|r#type| r#type.is_constructable_type())
+        .collect_vec();
+    if components.is_empty() {
+        return None;
+    }
+    let mut deps = HashSet::new();
+    for r#type in components {
+        for &(_, parameter) in r#type.get_constructor_parameters() {
+            deps.extend(env.get_deps(parameter).iter().cloned());
+        }
+    }
+    Some(signature_of_deps(analysis, &deps))
+}
+
+/// A substitution which is being written, as seen from the cursor.
+#[derive(Clone, Debug)]
+pub struct Call {
+    /// The code the substitution is applied to.
+    pub callee: String,
+    pub signature: Signature,
+    /// The indices in the signature of the parameters which have already
+    /// been given arguments.
+    pub assigned: Vec<usize>,
+    /// The index in the signature of the parameter whose argument is being
+    /// written at the cursor.
+    pub active_parameter: Option<usize>,
+}
+
+pub(super) fn resolve_item(analysis: &Analysis, context: ItemId, node: &Node) -> Option<ItemId> {
+    match node.phrase {
+        "identifier" => {
+            let ident = node.as_ident().ok()?;
+            analysis.env0.lookup_identifier(context, ident)
+        }
+        "member access" => {
+            let base = resolve_item(analysis, context, node.children[0].as_node())?;
+            let member = node.children[2].as_ident().ok()?;
+            let env = &analysis.env2;
+            let Def2::DStructLiteral(base) = &env[env.dereference(base)] else { return None };
+            base.get_field(member)
+        }
+        _ => None,
+    }
+}
+
+fn resolve_callee(analysis: &Analysis, context: ItemId, node: &Node) -> Option<Signature> {
+    if node.phrase == "member access" && node.children[2].as_ident().ok()? == "new" {
+        let r#type = resolve_item(analysis, context, node.children[0].as_node())?;
+        if let Some(signature) = constructor_signature(analysis, r#type) {
+            return Some(signature);
+        }
+    }
+    let item = resolve_item(analysis, context, node)?;
+    Some(signature(analysis, item))
+}
+
+/// Finds the substitution which is open at the end of the given code, which
+/// should be everything in a file up to the cursor. The analysis is only
+/// used to look up what the substitution is applied to, so it may be out of
+/// date as long as the code it is applied to has not changed.
+pub fn call_at(analysis: &Analysis, file_index: usize, code_before_cursor: &str) -> Option<Call> {
+    let parse_context = ParseContext::new();
+    let open = parser::open_substitution(code_before_cursor, &parse_context, file_index)?;
+    let range = open.base.position.range();
+    let callee = code_before_cursor[range.clone()]
+        .split_whitespace()
+        .join(" ");
+    let context = analysis.scope_at(file_index, range.start);
+    let signature = resolve_callee(analysis, context, &open.base)?;
+    let parameters = &signature.parameters;
+
+    let index_of_name = |name: &str| {
+        parameters
+            .iter()
+            .position(|parameter| parameter.name.as_deref() == Some(name))
+    };
+    // Arguments are told apart by their index, so that positional arguments
+    // can be assigned the same way as when the substitution is resolved.
+    let named = open
+        .arguments
+        .iter()
+        .enumerate()
+        .filter_map(|(index, argument)| match argument {
+            OpenArgument::Named(name) => {
+                Some((parameters[index_of_name(name)?].parameter.clone(), index))
+            }
+            OpenArgument::Positional => None,
+        })
+        .collect_vec();
+    let positional = (0..open.arguments.len())
+        .filter(|&index| matches!(open.arguments[index], OpenArgument::Positional))
+        .collect_vec();
+    let assign = |positional: Vec<usize>| {
+        let parameters = parameters.iter().map(|p| p.parameter.clone());
+        ArgumentAssignment::new(&analysis.env2, parameters, named.clone(), positional).ok()
+    };
+    let index_of_parameter =
+        |parameter: &ParameterPtr| parameters.iter().position(|p| &p.parameter == parameter);
+    // Finds the parameter the argument with the given index is assigned to.
+    let parameter_of = |assignment: Option<ArgumentAssignment<usize>>, argument: usize| {
+        let assignment = assignment?;
+        let (parameter, _) = assignment
+            .explicit
+            .iter()
+            .find(|&&(_, index)| index == argument)?;
+        index_of_parameter(parameter)
+    };
+    let assigned = assign(positional.clone())
+        .map(|assignment| assignment.explicit)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(parameter, _)| index_of_parameter(parameter))
+        .collect_vec();
+
+    let ends_argument = code_before_cursor.ends_with(|c: char| c.is_whitespace() || c == ',');
+    let active_parameter = match open.arguments.last() {
+        Some(OpenArgument::Named(name)) if !ends_argument || !open.last_argument_complete => {
+            index_of_name(name)
+        }
+        Some(OpenArgument::Positional) if !ends_argument => {
+            parameter_of(assign(positional), open.arguments.len() - 1)
+        }
+        // Otherwise, the cursor is where the next positional argument would
+        // be written.
+        _ => {
+            let next = open.arguments.len();
+            let with_next = positional.into_iter().chain([next]).collect();
+            parameter_of(assign(with_next), next)
+        }
+    };
+    Some(Call {
+        callee,
+        signature,
+        assigned,
+        active_parameter,
+    })
+}
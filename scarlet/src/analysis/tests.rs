@@ -0,0 +1,230 @@
+use super::*;
+use crate::environment::tests::{analyze, analyze_files, PRELUDE};
+
+#[test]
+fn names_are_classified_by_what_they_refer_to() {
+    let analysis = analyze(
+        "
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        boxed IS Box(Bool).new(true)
+        inner IS boxed.contents
+        ",
+    );
+    let tokens = semantic_tokens(&analysis)
+        .into_iter()
+        .filter(|token| token.position.range().start > PRELUDE.len())
+        .map(|token| {
+            let text = analysis.sources.text(token.position).unwrap().to_owned();
+            (text, token.kind, token.declaration, token.language_item)
+        })
+        .collect_vec();
+    let expected = [
+        ("Contents", TokenKind::Parameter, true, false),
+        ("Type", TokenKind::Type, false, false),
+        ("Box", TokenKind::Type, true, false),
+        ("contents", TokenKind::Parameter, true, false),
+        ("Contents", TokenKind::Parameter, false, false),
+        ("boxed", TokenKind::Value, true, false),
+        ("Box", TokenKind::Type, false, false),
+        ("Bool", TokenKind::Type, false, true),
+        ("new", TokenKind::Constructor, false, false),
+        ("true", TokenKind::Constructor, false, true),
+        ("inner", TokenKind::Value, true, false),
+        ("boxed", TokenKind::Value, false, false),
+        ("contents", TokenKind::Parameter, false, false),
+    ]
+    .map(|(text, kind, declaration, language_item)| {
+        (text.to_owned(), kind, declaration, language_item)
+    });
+    assert_eq!(tokens, expected);
+}
+
+/// Finds the substitution open at the end of the given code, written after
+/// the prelude.
+fn call_after_prelude(analysis: &Analysis, code: &str) -> Call {
+    let code = format!("{}\n{}", PRELUDE, code);
+    call_at(analysis, 1, &code).expect(&code)
+}
+
+fn parameter_names(call: &Call, indices: &[usize]) -> Vec<String> {
+    indices
+        .iter()
+        .map(|&index| call.signature.parameters[index].name.clone().unwrap())
+        .collect()
+}
+
+const CHOOSE: &str = "
+Contents IS ANY Type
+Box IS NEW_TYPE(contents IS ANY Contents)
+c IS ANY Bool
+x IS ANY Bool
+y IS ANY Bool
+choose IS if_then_else(c x y)
+";
+
+#[test]
+fn signature_help_follows_argument_assignment() {
+    let analysis = analyze(CHOOSE);
+    let choose = analysis.item_at_path("choose").unwrap();
+    let (label, ranges) = signature(&analysis, choose).label("choose");
+    assert_eq!(label, "choose(c IS ANY Bool, x IS ANY Bool, y IS ANY Bool)");
+    assert_eq!(&label[ranges[1].clone()], "x IS ANY Bool");
+
+    let call = call_after_prelude(&analysis, &format!("{}main IS choose(true ", CHOOSE));
+    assert_eq!(call.callee, "choose");
+    assert_eq!(parameter_names(&call, &call.assigned), ["c"]);
+    let active = call.active_parameter.unwrap();
+    assert_eq!(parameter_names(&call, &[active]), ["x"]);
+
+    let call = call_after_prelude(&analysis, &format!("{}main IS choose(y IS fa", CHOOSE));
+    let active = call.active_parameter.unwrap();
+    assert_eq!(parameter_names(&call, &[active]), ["y"]);
+
+    let call = call_after_prelude(&analysis, &format!("{}main IS Box.new(", CHOOSE));
+    let active = call.active_parameter.unwrap();
+    assert_eq!(parameter_names(&call, &[active]), ["contents"]);
+}
+
+#[test]
+fn positional_arguments_are_annotated_with_their_parameters() {
+    let analysis = analyze(&format!("{}main IS choose(true false true)", CHOOSE));
+    let hints = argument_hints(&analysis);
+    let names = hints
+        .iter()
+        .filter_map(|hint| hint.parameter_name.as_deref())
+        .collect_vec();
+    assert_eq!(names, ["c", "x", "y"]);
+    let (_, annotated) = annotate(&analysis.file_tree, &hints).remove(0);
+    assert!(annotated.ends_with("main IS choose(c IS true x IS false y IS true)"));
+}
+
+/// Returns the labels and kinds of what could be written at the end of the
+/// given code, written after the prelude.
+fn completions_after_prelude(analysis: &Analysis, code: &str) -> Vec<(String, CompletionKind)> {
+    let code = format!("{}\n{}", PRELUDE, code);
+    completions(analysis, 1, &code)
+        .into_iter()
+        .map(|completion| (completion.label, completion.kind))
+        .collect()
+}
+
+#[test]
+fn completes_identifiers_members_and_named_arguments() {
+    let source = format!(
+        "{}
+        boxed IS Box(Bool).new(true)
+        colors IS [red IS UNIQUE green IS UNIQUE]
+        ",
+        CHOOSE
+    );
+    let analysis = analyze(&source);
+    let complete =
+        |code: &str| completions_after_prelude(&analysis, &format!("{}{}", source, code));
+
+    let completions = complete("main IS ch");
+    assert_eq!(completions[0], ("choose".to_owned(), CompletionKind::Value));
+    let completions = complete("main IS col");
+    assert_eq!(
+        completions[0],
+        ("colors".to_owned(), CompletionKind::Module)
+    );
+    // Names which only match when ignoring case come after the others.
+    let labels = complete("main IS c")
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect_vec();
+    let contents = labels.iter().position(|label| label == "Contents").unwrap();
+    let choose = labels.iter().position(|label| label == "choose").unwrap();
+    assert!(choose < contents);
+
+    let completions = complete("main IS choose(true ");
+    assert_eq!(
+        completions[..2],
+        [
+            ("x".to_owned(), CompletionKind::NamedArgument),
+            ("y".to_owned(), CompletionKind::NamedArgument),
+        ]
+    );
+    assert!(!completions.contains(&("c".to_owned(), CompletionKind::NamedArgument)));
+
+    let completions = complete("main IS colors.");
+    assert_eq!(
+        completions,
+        [
+            ("red".to_owned(), CompletionKind::Value),
+            ("green".to_owned(), CompletionKind::Value),
+        ]
+    );
+    let completions = complete("main IS Box.");
+    assert_eq!(
+        completions,
+        [("new".to_owned(), CompletionKind::Constructor)]
+    );
+    let completions = complete("main IS boxed.");
+    assert_eq!(
+        completions,
+        [("contents".to_owned(), CompletionKind::Value)]
+    );
+}
+
+/// Returns the names and kinds of the symbols, with their children indented
+/// below them.
+fn describe_symbols(symbols: &[Symbol], depth: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for symbol in symbols {
+        let indent = "  ".repeat(depth);
+        lines.push(format!(
+            "{}{} ({})",
+            indent,
+            symbol.name,
+            symbol.kind.name()
+        ));
+        lines.extend(describe_symbols(&symbol.children, depth + 1));
+    }
+    lines
+}
+
+#[test]
+fn outlines_nest_symbols_inside_structures_and_files() {
+    let analysis = analyze_files(
+        "
+        shapes IS [
+            Circle IS NEW_TYPE()
+            unit IS Circle.new
+        ]
+        ",
+        &[("colors", "red IS UNIQUE\nmixed IS [purple IS ANY Bool]")],
+    );
+    let outline = outline(&analysis);
+    let names = outline.iter().map(|symbol| &symbol.name[..]).collect_vec();
+    assert_eq!(names[names.len() - 2..], ["shapes", "colors"]);
+    assert_eq!(
+        describe_symbols(&outline[names.len() - 2..], 0),
+        [
+            "shapes (module)",
+            "  Circle (type)",
+            "  unit (constructor)",
+            "colors (module)",
+            "  red (constructor)",
+            "  mixed (module)",
+            "    purple (parameter)",
+        ]
+    );
+    let shapes = &outline[names.len() - 2];
+    let text = |position| analysis.sources.text(position).unwrap();
+    assert_eq!(text(shapes.label_position.unwrap()), "shapes");
+    assert!(text(shapes.position.unwrap()).starts_with("shapes IS ["));
+    // Modules made from files are not written anywhere.
+    assert_eq!(outline[names.len() - 1].position, None);
+
+    let file_outline = file_outline(&analysis, 2);
+    assert_eq!(
+        describe_symbols(&file_outline, 0),
+        [
+            "red (constructor)",
+            "mixed (module)",
+            "  purple (parameter)"
+        ]
+    );
+}
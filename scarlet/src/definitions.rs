@@ -7,3 +7,6 @@ pub mod member_access;
 pub mod substitution;
 pub mod other;
 pub mod constructor;
+pub mod type_of;
+pub mod matchh;
+pub mod integer;
@@ -17,13 +17,20 @@ use crate::{
     shared::TripleBool,
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Builtin {
     IsExactly,
     IsSubtypeOf,
     IfThenElse,
     Union,
     GodType,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    IsLessThan,
+    IsAtMost,
 }
 
 impl Builtin {
@@ -34,6 +41,13 @@ impl Builtin {
             Self::IfThenElse => "if_then_else",
             Self::Union => "Union",
             Self::GodType => "Type",
+            Self::Add => "add",
+            Self::Subtract => "subtract",
+            Self::Multiply => "multiply",
+            Self::Divide => "divide",
+            Self::Remainder => "remainder",
+            Self::IsLessThan => "is_less_than",
+            Self::IsAtMost => "is_at_most",
         }
     }
 
@@ -44,6 +58,13 @@ impl Builtin {
             Builtin::IfThenElse => &["Result", "condition", "true_result", "false_result"],
             Builtin::Union => &["Subtype0", "Subtype1"],
             Builtin::GodType => &[],
+            Builtin::Add
+            | Builtin::Subtract
+            | Builtin::Multiply
+            | Builtin::Divide
+            | Builtin::Remainder
+            | Builtin::IsLessThan
+            | Builtin::IsAtMost => &["Left", "Right", "left", "right"],
         }
     }
 }
@@ -64,6 +85,18 @@ impl DBuiltin {
         Ok(Self { builtin, args })
     }
 
+    pub fn is_exactly(
+        comparee_type: ItemId,
+        comparand_type: ItemId,
+        comparee: ItemId,
+        comparand: ItemId,
+    ) -> Self {
+        Self {
+            builtin: Builtin::IsExactly,
+            args: vec![comparee_type, comparand_type, comparee, comparand],
+        }
+    }
+
     pub fn is_subtype_of(subtype: ItemId, supertype: ItemId) -> Self {
         Self {
             builtin: Builtin::IsSubtypeOf,
@@ -133,6 +166,23 @@ impl DBuiltin {
                 env.assert_of_type(subtype_1, god_type);
             }
             Builtin::GodType => {}
+            Builtin::Add
+            | Builtin::Subtract
+            | Builtin::Multiply
+            | Builtin::Divide
+            | Builtin::Remainder
+            | Builtin::IsLessThan
+            | Builtin::IsAtMost => {
+                let god_type = env.god_type();
+                let left_type = self.args[0];
+                let right_type = self.args[1];
+                let left = self.args[2];
+                let right = self.args[3];
+                env.assert_of_type(left_type, god_type);
+                env.assert_of_type(right_type, god_type);
+                env.assert_of_type(left, left_type);
+                env.assert_of_type(right, right_type);
+            }
         }
     }
 }
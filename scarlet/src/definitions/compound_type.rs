@@ -7,16 +7,20 @@ use std::{
 
 use itertools::Itertools;
 use maplit::hashmap;
+use num::{BigInt, Signed};
 
 use super::parameter::ParameterPtr;
 use crate::{
     environment::{Def2, Env2, Environment, ItemId},
+    shared::TripleBool,
     util::PtrExtension,
 };
 
 #[derive(Clone, Debug)]
 pub enum TypeId {
     GodType,
+    Natural,
+    Negative,
     UserType(Rc<()>),
 }
 
@@ -24,6 +28,8 @@ impl PartialEq for TypeId {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::GodType, Self::GodType) => true,
+            (Self::Natural, Self::Natural) => true,
+            (Self::Negative, Self::Negative) => true,
             (Self::UserType(type_id), Self::UserType(other_type_id)) => {
                 Rc::ptr_eq(type_id, other_type_id)
             }
@@ -46,6 +52,11 @@ impl Hash for TypeId {
 #[derive(Clone, Debug, PartialEq, Hash)]
 pub enum Type {
     GodType,
+    /// Integers which are zero or more. These are the values of `Nat`.
+    Natural,
+    /// Integers which are less than zero. Together with `Natural`, these are
+    /// the values of `Int`.
+    Negative,
     ModuleType {
         type_id: TypeId,
         declarations: Vec<String>,
@@ -77,15 +88,21 @@ impl Type {
     pub fn get_type_id(&self) -> TypeId {
         match self {
             Self::GodType => TypeId::GodType,
+            Self::Natural => TypeId::Natural,
+            Self::Negative => TypeId::Negative,
             Self::ModuleType { type_id, .. } => type_id.clone(),
             Self::UserType { type_id, .. } => type_id.clone(),
         }
     }
 
-    /// If you get "false", it means we don't know if it's a subtype, not
-    /// necessarily that it's guaranteed to not be a subtype.
-    pub fn is_subtype_of(&self, other: &DCompoundType) -> bool {
-        other.component_types.contains_key(&self.get_type_id())
+    /// Types are identified exactly, so this is always known. Checks only
+    /// become undecidable once arguments which could not be computed are
+    /// involved.
+    pub fn is_subtype_of(&self, other: &DCompoundType) -> TripleBool {
+        other
+            .component_types
+            .contains_key(&self.get_type_id())
+            .into()
     }
 
     pub fn parameters(&self, env: &Env2) -> Vec<ParameterPtr> {
@@ -108,6 +125,8 @@ pub struct DCompoundType {
     component_types: HashMap<TypeId, Rc<Type>>,
 }
 
+/// Compound types are the same type when they have the same components. The
+/// order components were added in does not matter.
 impl PartialEq for DCompoundType {
     fn eq(&self, other: &Self) -> bool {
         if self.component_types.len() != other.component_types.len() {
@@ -149,15 +168,15 @@ impl DCompoundType {
         self.component_types.len() == 1 && self.component_types.contains_key(&TypeId::GodType)
     }
 
-    /// If you get "false", it means we don't know if it's a subtype, not
-    /// necessarily that it's guaranteed to not be a subtype.
-    pub fn is_subtype_of(&self, other: &Self) -> bool {
-        for (key, _) in &self.component_types {
-            if !other.component_types.contains_key(key) {
-                return false;
-            }
-        }
-        true
+    /// Checks that every component of this type is also a component of the
+    /// other one.
+    pub fn is_subtype_of(&self, other: &Self) -> TripleBool {
+        TripleBool::and(
+            self.component_types
+                .values()
+                .map(|component| component.is_subtype_of(other))
+                .collect(),
+        )
     }
 
     pub fn parameters(&self, env: &Env2) -> Vec<ParameterPtr> {
@@ -172,6 +191,24 @@ impl DCompoundType {
         Self::new_single(Rc::new(Type::GodType))
     }
 
+    pub(crate) fn nat() -> Self {
+        Self::new_single(Rc::new(Type::Natural))
+    }
+
+    /// `Nat` is a subtype of this, since it has all of its components.
+    pub(crate) fn int() -> Self {
+        Self::nat().union(&Self::new_single(Rc::new(Type::Negative)))
+    }
+
+    /// Returns the type of an integer value.
+    pub(crate) fn of_integer(value: &BigInt) -> Self {
+        if value.is_negative() {
+            Self::new_single(Rc::new(Type::Negative))
+        } else {
+            Self::nat()
+        }
+    }
+
     pub fn get_single_type(&self) -> Option<&Rc<Type>> {
         if self.component_types.len() == 1 {
             Some(&self.component_types.values().next().unwrap())
@@ -0,0 +1,17 @@
+use num::BigInt;
+
+/// An integer written in the code, like `42`. It has the type `Nat`.
+#[derive(Clone, Debug)]
+pub struct DInteger {
+    value: BigInt,
+}
+
+impl DInteger {
+    pub fn new(value: BigInt) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+}
@@ -0,0 +1,74 @@
+use crate::environment::ItemId;
+
+/// One case of a `MATCH`, written `pattern => result` or
+/// `label IS pattern => result`.
+#[derive(Clone, Debug)]
+pub struct Case {
+    /// The name the result can use to refer to the value being matched, or
+    /// an empty string if the case does not name it.
+    label: String,
+    /// The type the value needs to have for this case to be chosen.
+    pattern: ItemId,
+    /// A parameter of type `pattern` which is given the value being matched
+    /// when this case is chosen.
+    binding: ItemId,
+    result: ItemId,
+}
+
+impl Case {
+    pub fn new(label: String, pattern: ItemId, binding: ItemId, result: ItemId) -> Self {
+        Self {
+            label,
+            pattern,
+            binding,
+            result,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn pattern(&self) -> ItemId {
+        self.pattern
+    }
+
+    pub fn binding(&self) -> ItemId {
+        self.binding
+    }
+
+    pub fn result(&self) -> ItemId {
+        self.result
+    }
+}
+
+/// Evaluates to the result of the first case whose pattern includes the type
+/// of the value being matched.
+#[derive(Clone, Debug)]
+pub struct DMatch {
+    subject: ItemId,
+    cases: Vec<Case>,
+}
+
+impl DMatch {
+    pub fn new(subject: ItemId, cases: Vec<Case>) -> Self {
+        Self { subject, cases }
+    }
+
+    pub fn subject(&self) -> ItemId {
+        self.subject
+    }
+
+    pub fn cases(&self) -> &[Case] {
+        &self.cases
+    }
+
+    /// Returns what `label` refers to inside `result`, if it is the result of
+    /// a case which names the value being matched that.
+    pub fn get_binding(&self, result: ItemId, label: &str) -> Option<ItemId> {
+        self.cases
+            .iter()
+            .find(|case| case.result == result && !case.label.is_empty() && case.label == label)
+            .map(|case| case.binding)
+    }
+}
@@ -21,6 +21,20 @@ pub struct Order {
     minor_order: u32,
 }
 
+impl Order {
+    pub fn major_order(&self) -> u8 {
+        self.major_order
+    }
+
+    pub fn file_order(&self) -> u32 {
+        self.file_order
+    }
+
+    pub fn minor_order(&self) -> u32 {
+        self.minor_order
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Parameter {
     order: Order,
@@ -62,6 +76,23 @@ impl DParameter {
         }
     }
 
+    /// Returns a new, distinct parameter which is identical to this one except
+    /// for its major order.
+    pub fn with_major_order(&self, major_order: u8) -> Self {
+        let order = Order {
+            major_order,
+            ..self.parameter.order
+        };
+        let parameter = Rc::new(Parameter {
+            order,
+            original_type: self.parameter.original_type,
+        });
+        Self {
+            parameter,
+            reduced_type: self.reduced_type,
+        }
+    }
+
     pub fn get_parameter_ptr(&self) -> ParameterPtr {
         Rc::clone(&self.parameter)
     }
@@ -11,8 +11,8 @@ use super::{
 };
 use crate::{
     definitions::{identifier::DIdentifier, member_access::DUnresolvedMemberAccess},
-    diagnostic::Diagnostic,
-    environment::{Env3, Environment, ItemId},
+    diagnostic::{Diagnostic, ErrorCode},
+    environment::{Def2, Def3, Env2, Env3, Environment, ItemId},
     shared::OrderedMap,
     util::PtrExtension,
 };
@@ -59,6 +59,9 @@ pub type PartiallyResolvedSubstitutions = OrderedMap<PartiallyResolvedTarget, It
 #[derive(Clone, Debug)]
 pub struct DPartiallyResolvedSubstitution {
     base: ItemId,
+    /// The item whose parameters the arguments are assigned to. This is the
+    /// base, except in `Type(args).new` where the arguments are the type's.
+    signature: ItemId,
     substitutions: PartiallyResolvedSubstitutions,
 }
 
@@ -66,6 +69,21 @@ impl DPartiallyResolvedSubstitution {
     pub fn new(base: ItemId, substitutions: PartiallyResolvedSubstitutions) -> Self {
         Self {
             base,
+            signature: base,
+            substitutions,
+        }
+    }
+
+    /// Substitutes into the constructor of a type using arguments written
+    /// for the type itself.
+    pub fn new_constructor(
+        constructor: ItemId,
+        r#type: ItemId,
+        substitutions: PartiallyResolvedSubstitutions,
+    ) -> Self {
+        Self {
+            base: constructor,
+            signature: r#type,
             substitutions,
         }
     }
@@ -77,6 +95,141 @@ impl DPartiallyResolvedSubstitution {
     pub fn base(&self) -> ItemId {
         self.base
     }
+
+    /// Decides which parameter each argument is assigned to, as described by
+    /// [`ArgumentAssignment::new`].
+    pub fn assign_arguments(&self, env: &Env2) -> Result<ArgumentAssignment, Diagnostic> {
+        let mut named = Vec::new();
+        let mut positional = Vec::new();
+        for (target, value) in &self.substitutions {
+            match target {
+                PartiallyResolvedTarget::Positional => positional.push(*value),
+                &PartiallyResolvedTarget::Item(target) => {
+                    let Def2::DParameter(p) = &env[env.dereference(target)] else {
+                        return Err(Diagnostic::new()
+                            .with_code(ErrorCode::NamedArgumentNotParameter)
+                            .with_text_error(
+                                "This argument is given a name which does not refer to a \
+                                 parameter, so there is nothing to substitute it for:"
+                                    .to_owned(),
+                            )
+                            .with_item_error(*value, env)
+                            .with_item_secondary_label(
+                                target,
+                                env,
+                                "the name refers to this, which is not a parameter".to_owned(),
+                            ));
+                    };
+                    named.push((p.get_parameter_ptr(), *value));
+                }
+            }
+        }
+        let parameters = env.get_deps(self.signature).iter().cloned();
+        ArgumentAssignment::new(env, parameters, named, positional).map_err(|value| {
+            Diagnostic::new()
+                .with_code(ErrorCode::TooManyArguments)
+                .with_text_error(
+                    "A substitution requires fewer arguments. All parameters have been \
+                     substituted, leaving this argument with no corresponding parameter:"
+                        .to_owned(),
+                )
+                .with_item_error(value, env)
+        })
+    }
+}
+
+/// Returns the parameters in the list which are the type of another parameter
+/// in the list.
+pub fn type_parameters(env: &Env2, parameters: &[ParameterPtr]) -> Vec<ParameterPtr> {
+    parameters
+        .iter()
+        .filter(|&p| {
+            parameters
+                .iter()
+                .any(|q| q != p && type_parameter_of(env, q).as_ref() == Some(p))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns the parameter which is the type of the given parameter, if any.
+fn type_parameter_of(env: &Env2, param: &ParameterPtr) -> Option<ParameterPtr> {
+    if let Def2::DParameter(p) = &env[env.dereference(param.original_type())] {
+        Some(p.get_parameter_ptr())
+    } else {
+        None
+    }
+}
+
+/// The result of matching the arguments of a substitution to the parameters
+/// of its base. Arguments are usually items, but can be anything which tells
+/// them apart, like their index in code which is still being written.
+#[derive(Clone, Debug)]
+pub struct ArgumentAssignment<Arg = ItemId> {
+    /// Parameters paired with the argument the user wrote for them. Named
+    /// arguments come first, followed by positional arguments in the order
+    /// they were written.
+    pub explicit: Vec<(ParameterPtr, Arg)>,
+    /// Parameters which were not given an argument but can be inferred from
+    /// the listed arguments, as the union of all of their types.
+    pub inferred: Vec<(ParameterPtr, Vec<Arg>)>,
+    /// Parameters which are left unsubstituted.
+    pub remaining: Vec<ParameterPtr>,
+}
+
+impl<Arg: Copy> ArgumentAssignment<Arg> {
+    /// Named arguments are assigned first, then positional arguments are
+    /// assigned in order to the remaining parameters with the lowest order.
+    /// Parameters which are the type of another parameter never take
+    /// positional arguments. They are inferred from the arguments given to the
+    /// parameters they are the type of, and can only be given explicitly with
+    /// a named argument. If there are more positional arguments than
+    /// parameters to assign them to, returns the first one left over.
+    pub fn new(
+        env: &Env2,
+        parameters: impl IntoIterator<Item = ParameterPtr>,
+        named: Vec<(ParameterPtr, Arg)>,
+        positional: Vec<Arg>,
+    ) -> Result<Self, Arg> {
+        let mut remaining = parameters.into_iter().collect_vec();
+        remaining.sort_by_key(|p| p.order());
+        let inferable = type_parameters(env, &remaining);
+
+        let mut explicit = named;
+        remaining.retain(|p| !explicit.iter().any(|(target, _)| target == p));
+        let slots = remaining
+            .iter()
+            .filter(|p| !inferable.contains(p))
+            .cloned()
+            .collect_vec();
+        for (index, value) in positional.into_iter().enumerate() {
+            let Some(target) = slots.get(index) else { return Err(value) };
+            remaining.retain(|x| x != target);
+            explicit.push((target.ptr_clone(), value));
+        }
+
+        let mut inferred = Vec::new();
+        for param in inferable {
+            if !remaining.contains(&param) {
+                continue;
+            }
+            let candidates = explicit
+                .iter()
+                .filter(|(target, _)| type_parameter_of(env, target).as_ref() == Some(&param))
+                .map(|(_, value)| *value)
+                .collect_vec();
+            if !candidates.is_empty() {
+                remaining.retain(|x| x != &param);
+                inferred.push((param, candidates));
+            }
+        }
+
+        Ok(Self {
+            explicit,
+            inferred,
+            remaining,
+        })
+    }
 }
 
 pub type Substitutions = OrderedMap<ParameterPtr, ItemId>;
@@ -105,15 +258,31 @@ impl DSubstitution {
         self.base
     }
 
+    pub fn do_asserts(&self) -> bool {
+        self.do_asserts
+    }
+
     pub fn add_type_asserts(&self, env: &mut Env3) {
         if !self.do_asserts {
             return;
         }
+        // Arguments given to the base, like the type's arguments in
+        // `Type(args).new(fields)`, also apply to the types of the parameters
+        // substituted here.
+        let mut all_substitutions = self.substitutions.clone();
+        let mut base = env.dereference(self.base);
+        while let Def3::DSubstitution(inner) = &env[base] {
+            for (target, value) in inner.substitutions() {
+                if !all_substitutions.contains_key(target) {
+                    all_substitutions.insert(target.ptr_clone(), *value);
+                }
+            }
+            base = env.dereference(inner.base);
+        }
         for (target, value) in &self.substitutions {
             let target_type = target.original_type();
             let target_type_deps = env.get_deps(target_type);
-            let target_type_subs: Substitutions = self
-                .substitutions
+            let target_type_subs: Substitutions = all_substitutions
                 .iter()
                 .filter(|(k, _)| target_type_deps.contains(k))
                 .cloned()
@@ -121,7 +290,7 @@ impl DSubstitution {
             if target_type_subs.is_empty() {
                 env.assert_of_type(*value, target_type);
             } else {
-                let subbed_target_type = env.new_defined_item(Self {
+                let subbed_target_type = env.intern(Self {
                     base: target_type,
                     substitutions: target_type_subs,
                     do_asserts: false,
@@ -0,0 +1,19 @@
+use crate::environment::ItemId;
+
+/// Evaluates to the type of another item. This is used wherever a type
+/// argument is left for the compiler to fill in, such as the type arguments of
+/// `is_exactly` when it is written as `a = b`.
+#[derive(Clone, Debug)]
+pub struct DTypeOf {
+    base: ItemId,
+}
+
+impl DTypeOf {
+    pub fn new(base: ItemId) -> Self {
+        Self { base }
+    }
+
+    pub fn base(&self) -> ItemId {
+        self.base
+    }
+}
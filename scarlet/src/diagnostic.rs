@@ -1,13 +1,15 @@
-use std::{
-    fmt::{self, Display, Formatter},
-    ops::Range,
-};
+mod code;
+
+use std::{collections::BTreeSet, ops::Range};
 
+pub use code::ErrorCode;
 use colored::{ColoredString, Colorize};
+use itertools::Itertools;
 
 use crate::{
     environment::{Env3, Environment, ItemId, Def3},
-    file_tree::FileNode, definitions::builtin::{DBuiltin, Builtin},
+    definitions::builtin::{DBuiltin, Builtin},
+    source_map::SourceMap,
 };
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
@@ -34,12 +36,6 @@ pub struct Position {
     end: usize,
 }
 
-impl Display for Position {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "F#{}[{}:{}]", self.file_index, self.start, self.end)
-    }
-}
-
 impl Position {
     pub fn new(file_index: usize, range: Range<usize>) -> Self {
         Self {
@@ -71,116 +67,290 @@ impl Position {
     }
 }
 
+/// A part of the code pointed out by a diagnostic.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Span {
+    pub position: Position,
+    pub label: Option<String>,
+    /// Whether this is what the diagnostic is about, rather than something
+    /// related to it. Primary spans are underlined with `^` and secondary
+    /// spans with `-`.
+    pub primary: bool,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum NoteKind {
+    Note,
+    Help,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Element {
     Text(String),
     GeneratedCodeBlock(String),
-    SourceCodeBlock(Position),
+    /// Code from a single file, with every span underlined.
+    SourceCodeBlock(Vec<Span>),
+    Note(NoteKind, String),
 }
 
-fn expand_text_range_to_include_full_lines(range: Range<usize>, text: &str) -> Range<usize> {
-    let mut last_line_before_dr = 0;
-    for (index, byte) in text.bytes().enumerate() {
-        if byte == ('\n' as u8) {
-            last_line_before_dr = index + 1;
+/// How many lines are shown at the start and at the end of a span which
+/// covers too many lines to show all of them.
+const SPAN_CONTEXT_LINES: usize = 2;
+
+/// Whether diagnostics are rendered with colors for a terminal, or as plain
+/// text for logs and tests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(self, level: Level, text: &str) -> String {
+        if self.color {
+            level.colorize(text).to_string()
+        } else {
+            text.to_owned()
         }
-        if index >= range.start {
-            break;
+    }
+
+    fn bold(self, level: Option<Level>, text: &str) -> String {
+        match (self.color, level) {
+            (false, _) => text.to_owned(),
+            (true, Some(level)) => level.colorize(text).bold().to_string(),
+            (true, None) => text.bold().to_string(),
         }
     }
-    let mut first_line_after_dr = text.len();
-    for (index, byte) in text.bytes().enumerate() {
-        if index < range.end {
-            continue;
-        } else if byte == ('\r' as u8) || byte == ('\n' as u8) {
-            first_line_after_dr = index;
-            break;
+}
+
+fn render_line_underline(
+    level: Level,
+    spans: &[&Span],
+    line: Range<usize>,
+    text: &str,
+    style: Style,
+) -> Option<String> {
+    // Spans which continue from a previous line don't underline indentation.
+    let indent_end = line.start + text.len() - text.trim_start().len();
+    let mark_at = |offset: usize, is_end: bool| {
+        let covering = spans.iter().filter(|span| {
+            let range = span.position.range();
+            if range.is_empty() {
+                range.start == offset
+            } else {
+                let continued = range.start < line.start && offset < indent_end;
+                range.contains(&offset) && !continued && !is_end
+            }
+        });
+        covering.map(|span| span.primary).max()
+    };
+    let mut marks = Vec::new();
+    let mut offset = line.start;
+    for char in text.chars() {
+        marks.push((char, mark_at(offset, false)));
+        offset += char.len_utf8();
+    }
+    // Empty spans can point at the end of a line, after its last character.
+    marks.push((' ', mark_at(offset, true)));
+    let last_mark = marks.iter().rposition(|(_, mark)| mark.is_some())?;
+    marks.truncate(last_mark + 1);
+
+    let mut underline = String::new();
+    for (mark, run) in &marks.iter().group_by(|(_, mark)| *mark) {
+        let run: String = run
+            .map(|&(char, mark)| match (char, mark) {
+                ('\t', None) => '\t',
+                (_, None) => ' ',
+                (_, Some(true)) => '^',
+                (_, Some(false)) => '-',
+            })
+            .collect();
+        match mark {
+            Some(true) => underline.push_str(&style.paint(level, &run)),
+            Some(false) => underline.push_str(&style.paint(Level::Info, &run)),
+            None => underline.push_str(&run),
         }
     }
-    last_line_before_dr..first_line_after_dr
+    Some(underline)
 }
 
-fn find_line_and_column(index: usize, text: &str) -> (usize, usize) {
-    let mut line = 1;
-    let mut column = 1;
-    for char in (&text[..index]).chars() {
-        if char == '\n' {
-            line += 1;
-            column = 1;
+fn render_source_code_block(
+    level: Level,
+    spans: &[Span],
+    sources: &SourceMap,
+    style: Style,
+) -> String {
+    let main = spans.iter().find(|span| span.primary).unwrap_or(&spans[0]);
+    let Some(file) = sources.file(main.position.file_index()) else {
+        let header = format!("--> [generated] {:?}\n", main.position.range());
+        return style.paint(level, &header);
+    };
+    let line_of = |offset: usize| file.line_and_column(offset).0;
+    let last_line_of = |span: &Span| {
+        let range = span.position.range();
+        line_of(range.end.saturating_sub(1).max(range.start))
+    };
+    let mut lines = BTreeSet::new();
+    for span in spans {
+        let first = line_of(span.position.range().start);
+        let last = last_line_of(span);
+        if last - first < 2 * SPAN_CONTEXT_LINES {
+            lines.extend(first..=last);
         } else {
-            column += 1;
+            lines.extend(first..first + SPAN_CONTEXT_LINES);
+            lines.extend(last + 1 - SPAN_CONTEXT_LINES..=last);
         }
     }
-    (line, column)
+    let width = lines.iter().last().unwrap().to_string().len();
+    let gutter = |line: Option<usize>| {
+        let number = line.map(|line| line.to_string()).unwrap_or_default();
+        style.paint(level, &format!("{:>width$} | ", number, width = width))
+    };
+    let empty_gutter = gutter(None);
+
+    let location = sources.location(main.position).unwrap();
+    let arrow = style.paint(level, "-->");
+    let mut result = format!("{}{} {}\n", " ".repeat(width), arrow, location);
+    result.push_str(&format!("{}\n", empty_gutter.trim_end()));
+    let mut previous = None;
+    for line in lines {
+        if previous.map(|previous| line > previous + 1) == Some(true) {
+            result.push_str(&format!("{}\n", style.paint(level, "...")));
+        }
+        previous = Some(line);
+        let range = file.line_range(line);
+        let text = &file.content[range.clone()];
+        result.push_str(&format!("{}{}\n", gutter(Some(line)), text));
+
+        let on_line = spans
+            .iter()
+            .filter(|span| line_of(span.position.range().start) <= line)
+            .filter(|span| last_line_of(span) >= line)
+            .collect_vec();
+        let Some(underline) = render_line_underline(level, &on_line, range.clone(), text, style)
+        else {
+            continue;
+        };
+        // Labels go after the last line of their span. The rightmost one goes
+        // next to the underline if nothing else is underlined after it, and
+        // the others go below, connected to where their spans start.
+        let column_of = |span: &Span| {
+            let start = span.position.range().start.max(range.start);
+            file.content[range.start..start].chars().count()
+        };
+        let end_of = |span: &Span| span.position.range().end.min(range.end);
+        let underline_end = on_line.iter().map(|span| end_of(span)).max().unwrap();
+        let mut labels = on_line
+            .iter()
+            .filter(|span| last_line_of(span) == line)
+            .filter_map(|span| Some((column_of(span), end_of(span), span.label.as_ref()?)))
+            .collect_vec();
+        labels.sort_by_key(|&(column, end, _)| std::cmp::Reverse((column, end)));
+        result.push_str(&format!("{}{}", empty_gutter, underline));
+        match labels.first() {
+            Some(&(_, end, label)) if end == underline_end => {
+                result.push_str(&format!(" {}\n", style.bold(None, label)));
+                labels.remove(0);
+            }
+            _ => result.push_str("\n"),
+        }
+        let blank = text
+            .chars()
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect_vec();
+        let connectors = |width: usize, columns: &[usize]| -> String {
+            (0..width)
+                .map(|index| match columns.contains(&index) {
+                    true => '|',
+                    false => blank.get(index).copied().unwrap_or(' '),
+                })
+                .collect()
+        };
+        let columns = labels.iter().map(|&(column, _, _)| column).collect_vec();
+        if let Some(&rightmost) = columns.first() {
+            let row = connectors(rightmost + 1, &columns);
+            result.push_str(&format!("{}{}\n", empty_gutter, style.paint(level, &row)));
+        }
+        for (index, &(column, _, label)) in labels.iter().enumerate() {
+            let row = style.paint(level, &connectors(column, &columns[index + 1..]));
+            let label = style.bold(None, label);
+            result.push_str(&format!("{}{}{}\n", empty_gutter, row, label));
+        }
+    }
+    result
+}
+
+fn render_text(level: Level, text: &str, code: Option<ErrorCode>, style: Style) -> String {
+    let level_text = match level {
+        Level::Error => "ERROR",
+        Level::Warning => "WARN",
+        Level::Info => "INFO",
+    };
+    let level_text = match code {
+        Some(code) => format!("{}[{}]:", level_text, code.code()),
+        None => format!("{}:", level_text),
+    };
+    let level_text = style.bold(Some(level), &level_text);
+    format!("{} {}\n", level_text, style.bold(None, text))
 }
 
 impl Element {
-    pub fn format_colorful(&self, level: Level, files: &FileNode) -> String {
+    fn render(&self, level: Level, sources: &SourceMap, style: Style) -> String {
         match self {
-            Element::Text(text) => {
-                let level_text = match level {
-                    Level::Error => "ERROR:",
-                    Level::Warning => "WARN:",
-                    Level::Info => "INFO:",
-                };
-                format!("{} {}\n", level.colorize(level_text).bold(), text.bold())
-            }
+            Element::Text(text) => render_text(level, text, None, style),
             Element::GeneratedCodeBlock(generated) => {
-                let mut result = format!("{}", level.colorize(&format!("> [generated]\n")));
+                let mut result = style.paint(level, "--> [generated]\n");
                 for line in generated.lines() {
-                    result.push_str(&format!("{}{}\n", level.colorize("| "), line));
+                    result.push_str(&format!("{}{}\n", style.paint(level, "| "), line));
                 }
                 result
             }
-            Element::SourceCodeBlock(location) => {
-                let (path, content) = files.get_file(location.file_index());
-                let diagnostic_range = location.range();
-                let expanded_range =
-                    expand_text_range_to_include_full_lines(diagnostic_range.clone(), content);
-                let text = &content[expanded_range.clone()];
-                let (line, column) = find_line_and_column(diagnostic_range.start, content);
-                let mut result = format!(
-                    "{}",
-                    level.colorize(&format!("> {}.sr:{}:{}\n", path, line, column))
-                );
-                let mut position = expanded_range.start;
-                for line in text.lines() {
-                    result.push_str(&format!("{}{}\n", level.colorize("| "), line));
-                    let mut highlight = String::new();
-                    for char in line.chars() {
-                        if diagnostic_range.contains(&position) {
-                            highlight.push('^');
-                        } else {
-                            highlight.push(' ');
-                        }
-                        position += char.len_utf8();
-                    }
-                    if highlight.contains("^") {
-                        result.push_str(&format!(
-                            "{}{}\n",
-                            level.colorize("| "),
-                            level.colorize(&highlight)
-                        ));
-                    }
-                }
-                result
+            Element::SourceCodeBlock(spans) => {
+                render_source_code_block(level, spans, sources, style)
+            }
+            Element::Note(kind, text) => {
+                let kind = match kind {
+                    NoteKind::Note => "note:",
+                    NoteKind::Help => "help:",
+                };
+                format!("= {} {}\n", style.bold(None, kind), text)
             }
         }
     }
+
+    pub fn format_colorful(&self, level: Level, sources: &SourceMap) -> String {
+        self.render(level, sources, Style { color: true })
+    }
+
+    pub fn format_plain(&self, level: Level, sources: &SourceMap) -> String {
+        self.render(level, sources, Style { color: false })
+    }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Diagnostic {
+    code: Option<ErrorCode>,
     elements: Vec<(Level, Element)>,
 }
 
 impl Diagnostic {
     pub fn new() -> Self {
         Self {
+            code: None,
             elements: Vec::new(),
         }
     }
 
+    pub fn code(&self) -> Option<ErrorCode> {
+        self.code
+    }
+
+    /// Says which kind of diagnostic this is, so that it can be looked up with
+    /// `scarlet explain`.
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn with_element(mut self, level: Level, element: Element) -> Self {
         self.elements.push((level, element));
         self
@@ -226,7 +396,12 @@ impl Diagnostic {
         level: Level,
         source_code_block: impl Into<Position>,
     ) -> Self {
-        self.with_element(level, Element::SourceCodeBlock(source_code_block.into()))
+        let span = Span {
+            position: source_code_block.into(),
+            label: None,
+            primary: true,
+        };
+        self.with_element(level, Element::SourceCodeBlock(vec![span]))
     }
 
     pub fn with_source_code_block_info(self, source_code_block: impl Into<Position>) -> Self {
@@ -241,6 +416,72 @@ impl Diagnostic {
         self.with_source_code_block(Level::Error, source_code_block)
     }
 
+    /// Adds a span to the code block before this if it shows the same file,
+    /// or otherwise starts a new code block.
+    pub fn with_span(mut self, level: Level, span: Span) -> Self {
+        if let Some((_, Element::SourceCodeBlock(spans))) = self.elements.last_mut() {
+            if spans[0].position.file_index() == span.position.file_index() {
+                spans.push(span);
+                return self;
+            }
+        }
+        self.with_element(level, Element::SourceCodeBlock(vec![span]))
+    }
+
+    /// Points out what the diagnostic is about, explaining it with a label.
+    pub fn with_primary_label(self, level: Level, position: Position, label: String) -> Self {
+        let span = Span {
+            position,
+            label: Some(label),
+            primary: true,
+        };
+        self.with_span(level, span)
+    }
+
+    /// Points out code related to what the diagnostic is about, explaining
+    /// how it is related with a label.
+    pub fn with_secondary_label(self, position: Position, label: String) -> Self {
+        let span = Span {
+            position,
+            label: Some(label),
+            primary: false,
+        };
+        self.with_span(Level::Info, span)
+    }
+
+    pub fn with_item_primary_label<D>(
+        self,
+        level: Level,
+        item: ItemId,
+        env: &Environment<D>,
+        label: String,
+    ) -> Self {
+        match env.get_position(item) {
+            Some(position) => self.with_primary_label(level, position, label),
+            None => self.with_item(level, item, env),
+        }
+    }
+
+    pub fn with_item_secondary_label<D>(
+        self,
+        item: ItemId,
+        env: &Environment<D>,
+        label: String,
+    ) -> Self {
+        match env.get_position(item) {
+            Some(position) => self.with_secondary_label(position, label),
+            None => self.with_item(Level::Info, item, env),
+        }
+    }
+
+    pub fn with_note(self, text: String) -> Self {
+        self.with_element(Level::Info, Element::Note(NoteKind::Note, text))
+    }
+
+    pub fn with_help(self, text: String) -> Self {
+        self.with_element(Level::Info, Element::Note(NoteKind::Help, text))
+    }
+
     pub fn with_item<D>(self, level: Level, item: ItemId, env: &Environment<D>) -> Self {
         if let Some(position) = env.get_position(item) {
             self.with_source_code_block(level, position)
@@ -250,11 +491,11 @@ impl Diagnostic {
         }
     }
 
-    pub fn with_item_info(self, item: ItemId, env: &Env3) -> Self {
+    pub fn with_item_info<D>(self, item: ItemId, env: &Environment<D>) -> Self {
         Self::with_item(self, Level::Info, item, env)
     }
 
-    pub fn with_item_warning(self, item: ItemId, env: &Env3) -> Self {
+    pub fn with_item_warning<D>(self, item: ItemId, env: &Environment<D>) -> Self {
         Self::with_item(self, Level::Warning, item, env)
     }
 
@@ -264,11 +505,121 @@ impl Diagnostic {
 }
 
 impl Diagnostic {
-    pub fn format_colorful(&self, files: &FileNode) -> String {
+    fn render(&self, sources: &SourceMap, style: Style) -> String {
         let mut result = String::new();
-        for (level, element) in &self.elements {
-            result.push_str(&element.format_colorful(*level, files));
+        for (index, (level, element)) in self.elements.iter().enumerate() {
+            match element {
+                // The code goes next to the message which starts the diagnostic.
+                Element::Text(text) if index == 0 => {
+                    result.push_str(&render_text(*level, text, self.code, style))
+                }
+                _ => result.push_str(&element.render(*level, sources, style)),
+            }
+        }
+        if let Some(code) = self.code {
+            let text = format!(
+                "Run `scarlet explain {}` for more information.",
+                code.code()
+            );
+            let note = Element::Note(NoteKind::Note, text);
+            result.push_str(&note.render(Level::Info, sources, style));
         }
         result
     }
+
+    pub fn format_colorful(&self, sources: &SourceMap) -> String {
+        self.render(sources, Style { color: true })
+    }
+
+    /// Renders the diagnostic without colors, for logs, editors and tests.
+    pub fn format_plain(&self, sources: &SourceMap) -> String {
+        self.render(sources, Style { color: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_tree::FileNode;
+
+    const SOURCE: &str = "flag IS ANY Bool
+main IS flag(
+    UNIQUE
+)
+other IS [
+    a IS UNIQUE
+    b IS UNIQUE
+    c IS UNIQUE
+]
+";
+
+    fn sources() -> SourceMap {
+        let file_tree = FileNode {
+            self_content: SOURCE.to_owned(),
+            children: Vec::new(),
+        };
+        SourceMap::new("program", &file_tree)
+    }
+
+    /// Returns the position of the given text in the source.
+    fn position_of(text: &str) -> Position {
+        let start = SOURCE.find(text).unwrap();
+        Position::new(1, start..start + text.len())
+    }
+
+    #[test]
+    fn renders_labels_under_the_code() {
+        let diagnostic = Diagnostic::new()
+            .with_code(ErrorCode::TypeMismatch)
+            .with_text_error("This argument has the wrong type:".to_owned())
+            .with_primary_label(Level::Error, position_of("UNIQUE"), "this".to_owned())
+            .with_secondary_label(position_of("ANY Bool"), "needs a Bool".to_owned())
+            .with_secondary_label(position_of("flag("), "given here".to_owned())
+            .with_secondary_label(position_of("main"), "in main".to_owned())
+            .with_note("A note.".to_owned())
+            .with_help("Some help.".to_owned());
+        let expected = "\
+ERROR[E0012]: This argument has the wrong type:
+ --> program.sr:3:5
+  |
+1 | flag IS ANY Bool
+  |         -------- needs a Bool
+2 | main IS flag(
+  | ----    ----- given here
+  | |
+  | in main
+3 |     UNIQUE
+  |     ^^^^^^ this
+= note: A note.
+= help: Some help.
+= note: Run `scarlet explain E0012` for more information.
+";
+        assert_eq!(diagnostic.format_plain(&sources()), expected);
+    }
+
+    #[test]
+    fn shortens_long_spans() {
+        let start = SOURCE.find("other").unwrap();
+        let end = SOURCE.rfind(']').unwrap() + 1;
+        let diagnostic = Diagnostic::new()
+            .with_text_warning("This is long:".to_owned())
+            .with_source_code_block_warning(Position::new(1, start..end))
+            .with_source_code_block_info(Position::new(0, 3..5));
+        let expected = "\
+WARN: This is long:
+ --> program.sr:5:1
+  |
+5 | other IS [
+  | ^^^^^^^^^^
+6 |     a IS UNIQUE
+  |     ^^^^^^^^^^^
+...
+8 |     c IS UNIQUE
+  |     ^^^^^^^^^^^
+9 | ]
+  | ^
+--> [generated] 3..5
+";
+        assert_eq!(diagnostic.format_plain(&sources()), expected);
+    }
 }
@@ -0,0 +1,220 @@
+/// Identifies a kind of diagnostic, so that it can be looked up with
+/// `scarlet explain`. Codes are never reused once they have been assigned.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum ErrorCode {
+    UnrecognizedKeyword,
+    UnrecognizedInput,
+    IncompletePhrase,
+    ExpectedIdentifier,
+    InvalidOrder,
+    OrderOnNonParameter,
+    UnknownBuiltin,
+    DuplicateLanguageItem,
+    MissingLanguageItem,
+    TooManyArguments,
+    ConflictingInference,
+    TypeMismatch,
+    ParameterOrderTie,
+    InvalidNewName,
+    RenameUnlabeled,
+    RenameNameTaken,
+    RenameUnresolvedMember,
+    RenameChangesReference,
+    UndecidableAssert,
+    MalformedMatch,
+    NonExhaustiveMatch,
+    UnreachableCase,
+    NamedArgumentNotParameter,
+    InvalidMemberAccess,
+    ConflictingCaseTypes,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 25] = [
+        ErrorCode::UnrecognizedKeyword,
+        ErrorCode::UnrecognizedInput,
+        ErrorCode::IncompletePhrase,
+        ErrorCode::ExpectedIdentifier,
+        ErrorCode::InvalidOrder,
+        ErrorCode::OrderOnNonParameter,
+        ErrorCode::UnknownBuiltin,
+        ErrorCode::DuplicateLanguageItem,
+        ErrorCode::MissingLanguageItem,
+        ErrorCode::TooManyArguments,
+        ErrorCode::ConflictingInference,
+        ErrorCode::TypeMismatch,
+        ErrorCode::ParameterOrderTie,
+        ErrorCode::InvalidNewName,
+        ErrorCode::RenameUnlabeled,
+        ErrorCode::RenameNameTaken,
+        ErrorCode::RenameUnresolvedMember,
+        ErrorCode::RenameChangesReference,
+        ErrorCode::UndecidableAssert,
+        ErrorCode::MalformedMatch,
+        ErrorCode::NonExhaustiveMatch,
+        ErrorCode::UnreachableCase,
+        ErrorCode::NamedArgumentNotParameter,
+        ErrorCode::InvalidMemberAccess,
+        ErrorCode::ConflictingCaseTypes,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::UnrecognizedKeyword => "E0001",
+            ErrorCode::UnrecognizedInput => "E0002",
+            ErrorCode::IncompletePhrase => "E0003",
+            ErrorCode::ExpectedIdentifier => "E0004",
+            ErrorCode::InvalidOrder => "E0005",
+            ErrorCode::OrderOnNonParameter => "E0006",
+            ErrorCode::UnknownBuiltin => "E0007",
+            ErrorCode::DuplicateLanguageItem => "E0008",
+            ErrorCode::MissingLanguageItem => "E0009",
+            ErrorCode::TooManyArguments => "E0010",
+            ErrorCode::ConflictingInference => "E0011",
+            ErrorCode::TypeMismatch => "E0012",
+            ErrorCode::ParameterOrderTie => "E0013",
+            ErrorCode::InvalidNewName => "E0014",
+            ErrorCode::RenameUnlabeled => "E0015",
+            ErrorCode::RenameNameTaken => "E0016",
+            ErrorCode::RenameUnresolvedMember => "E0017",
+            ErrorCode::RenameChangesReference => "E0018",
+            ErrorCode::UndecidableAssert => "E0019",
+            ErrorCode::MalformedMatch => "E0020",
+            ErrorCode::NonExhaustiveMatch => "E0021",
+            ErrorCode::UnreachableCase => "E0022",
+            ErrorCode::NamedArgumentNotParameter => "E0023",
+            ErrorCode::InvalidMemberAccess => "E0024",
+            ErrorCode::ConflictingCaseTypes => "E0025",
+        }
+    }
+
+    /// Finds the kind of diagnostic with the given code, ignoring case.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    /// A long-form explanation of the diagnostic in Markdown, with example
+    /// programs.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ErrorCode::UnrecognizedKeyword => include_str!("explanations/E0001.md"),
+            ErrorCode::UnrecognizedInput => include_str!("explanations/E0002.md"),
+            ErrorCode::IncompletePhrase => include_str!("explanations/E0003.md"),
+            ErrorCode::ExpectedIdentifier => include_str!("explanations/E0004.md"),
+            ErrorCode::InvalidOrder => include_str!("explanations/E0005.md"),
+            ErrorCode::OrderOnNonParameter => include_str!("explanations/E0006.md"),
+            ErrorCode::UnknownBuiltin => include_str!("explanations/E0007.md"),
+            ErrorCode::DuplicateLanguageItem => include_str!("explanations/E0008.md"),
+            ErrorCode::MissingLanguageItem => include_str!("explanations/E0009.md"),
+            ErrorCode::TooManyArguments => include_str!("explanations/E0010.md"),
+            ErrorCode::ConflictingInference => include_str!("explanations/E0011.md"),
+            ErrorCode::TypeMismatch => include_str!("explanations/E0012.md"),
+            ErrorCode::ParameterOrderTie => include_str!("explanations/E0013.md"),
+            ErrorCode::InvalidNewName => include_str!("explanations/E0014.md"),
+            ErrorCode::RenameUnlabeled => include_str!("explanations/E0015.md"),
+            ErrorCode::RenameNameTaken => include_str!("explanations/E0016.md"),
+            ErrorCode::RenameUnresolvedMember => include_str!("explanations/E0017.md"),
+            ErrorCode::RenameChangesReference => include_str!("explanations/E0018.md"),
+            ErrorCode::UndecidableAssert => include_str!("explanations/E0019.md"),
+            ErrorCode::MalformedMatch => include_str!("explanations/E0020.md"),
+            ErrorCode::NonExhaustiveMatch => include_str!("explanations/E0021.md"),
+            ErrorCode::UnreachableCase => include_str!("explanations/E0022.md"),
+            ErrorCode::NamedArgumentNotParameter => include_str!("explanations/E0023.md"),
+            ErrorCode::InvalidMemberAccess => include_str!("explanations/E0024.md"),
+            ErrorCode::ConflictingCaseTypes => include_str!("explanations/E0025.md"),
+        }
+    }
+
+    /// The first line of the explanation, without the code.
+    pub fn title(&self) -> &'static str {
+        let first_line = self.explanation().lines().next().unwrap_or_default();
+        let title = first_line.trim_start_matches('#').trim_start();
+        title.split_once(": ").map_or(title, |(_, title)| title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analysis::Analysis,
+        diagnostic::{Diagnostic, Level},
+        file_tree::FileNode,
+        source_map::SourceMap,
+    };
+
+    /// Returns the codes of the errors which stop a program from compiling,
+    /// or of its warnings if it compiles.
+    fn codes_of(program: &str) -> Vec<ErrorCode> {
+        let file_tree = FileNode {
+            self_content: program.to_owned(),
+            children: Vec::new(),
+        };
+        let sources = SourceMap::new("example", &file_tree);
+        let errors = match Analysis::new(file_tree, sources).map(|analysis| analysis.env3) {
+            Err(diagnostics) | Ok(Err(diagnostics)) => diagnostics,
+            Ok(Ok(env)) => return env.warnings().iter().filter_map(Diagnostic::code).collect(),
+        };
+        errors
+            .iter()
+            .filter(|error| matches!(error.elements.first(), Some((Level::Error, _))))
+            .filter_map(Diagnostic::code)
+            .collect()
+    }
+
+    #[test]
+    fn codes_are_numbered_in_order() {
+        for (index, code) in ErrorCode::ALL.iter().enumerate() {
+            assert_eq!(code.code(), format!("E{:04}", index + 1));
+            assert_eq!(ErrorCode::from_code(code.code()), Some(*code));
+            let lowercase = code.code().to_lowercase();
+            assert_eq!(ErrorCode::from_code(&lowercase), Some(*code));
+        }
+        assert_eq!(ErrorCode::from_code("E9999"), None);
+    }
+
+    #[test]
+    fn explanations_start_with_their_code() {
+        for code in ErrorCode::ALL {
+            let heading = format!("# {}: {}\n", code.code(), code.title());
+            assert!(code.explanation().starts_with(&heading), "{:?}", code);
+            assert!(!code.title().is_empty(), "{:?}", code);
+        }
+    }
+
+    /// Codes whose examples are not whole programs which give them when
+    /// compiled. Renaming reports its own diagnostics, and parameters are
+    /// tied across more than one file.
+    const NOT_COMPILED: [ErrorCode; 6] = [
+        ErrorCode::ParameterOrderTie,
+        ErrorCode::InvalidNewName,
+        ErrorCode::RenameUnlabeled,
+        ErrorCode::RenameNameTaken,
+        ErrorCode::RenameUnresolvedMember,
+        ErrorCode::RenameChangesReference,
+    ];
+
+    /// The first example in each explanation shows the diagnostic, and the
+    /// ones after it show how to fix it.
+    #[test]
+    fn examples_give_the_diagnostics_they_explain() {
+        for code in ErrorCode::ALL {
+            if NOT_COMPILED.contains(&code) {
+                continue;
+            }
+            let examples = code.explanation().split("```scarlet\n").skip(1);
+            for (index, example) in examples.enumerate() {
+                let (program, _) = example.split_once("```").unwrap();
+                let codes = codes_of(program);
+                if index == 0 {
+                    assert_eq!(codes, [code], "{}", program);
+                } else {
+                    assert_eq!(codes, [], "{}", program);
+                }
+            }
+        }
+    }
+}
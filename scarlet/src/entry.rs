@@ -1,10 +1,16 @@
-use std::{collections::HashMap, time::Instant};
+use std::{collections::HashMap, fs, path::Path, time::Instant};
+
+use serde_json::json;
 
 use crate::{
+    analysis::{self, Analysis},
     definitions::struct_literal::DStructLiteral,
-    environment::{Environment, ENV},
-    file_tree,
+    diagnostic::ErrorCode,
+    environment::{Environment, UndecidableAsserts, ENV},
+    file_tree::{self, FileNode},
+    lsp,
     parser::{self, create_root, ParseContext},
+    source_map::SourceMap,
 };
 
 /// This struct guarantees certain parts of the code remain internal to the
@@ -12,11 +18,259 @@ use crate::{
 pub(crate) struct OnlyConstructedByEntry(());
 
 pub(crate) fn entry() {
-    let path = std::env::args().skip(1).next().unwrap_or(String::from("."));
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Commands which generate files can instead check that they are up to date.
+    let check = args.iter().any(|arg| arg == "--check");
+    args.retain(|arg| arg != "--check");
+    // Diagnostics are printed without colors, for logs.
+    if args.iter().any(|arg| arg == "--plain") {
+        colored::control::set_override(false);
+    }
+    args.retain(|arg| arg != "--plain");
+    // Type checks which depend on parameters with no value are warnings unless
+    // configured otherwise.
+    let undecidable_asserts = if args.iter().any(|arg| arg == "--deny-undecidable") {
+        UndecidableAsserts::Deny
+    } else if args.iter().any(|arg| arg == "--allow-undecidable") {
+        UndecidableAsserts::Allow
+    } else {
+        UndecidableAsserts::Warn
+    };
+    args.retain(|arg| arg != "--deny-undecidable" && arg != "--allow-undecidable");
+    let path_arg = |index: usize| args.get(index).cloned().unwrap_or(String::from("."));
+    match args.first().map(String::as_str) {
+        Some("annotate") => annotate(&path_arg(1)),
+        Some("doc") if args.len() == 3 => doc(&args[1], &args[2]),
+        Some("explain") => explain(args.get(1).map(String::as_str)),
+        Some("lsp") => lsp::serve(args.get(1).map(Path::new)),
+        Some("outline") => outline(&path_arg(1)),
+        Some("rename") if args.len() == 4 => rename(&args[1], &args[2], &args[3]),
+        Some("textmate") if args.len() >= 2 => textmate(&args[1], check),
+        Some("tokens") => tokens(&path_arg(1)),
+        Some("tree-sitter") if args.len() >= 2 => tree_sitter(&args[1], &args[2..], check),
+        _ => compile(&path_arg(0), undecidable_asserts),
+    }
+}
+
+/// Reads the program at the given path, printing why if it cannot be read.
+fn read_program(path: &str) -> Option<FileNode> {
+    match file_tree::read_root(path) {
+        Ok(file_tree) => Some(file_tree),
+        Err(error) => {
+            println!("Could not read a program from {}: {}", path, error);
+            None
+        }
+    }
+}
+
+/// Reads and analyzes the program at the given path, printing the diagnostics
+/// which stop it from being analyzed if there are any.
+fn load_analysis(path: &str) -> Option<(Analysis, SourceMap)> {
+    let file_tree = read_program(path)?;
+    let sources = SourceMap::new(path, &file_tree);
+    match Analysis::new(file_tree, sources.clone()) {
+        Ok(analysis) => Some((analysis, sources)),
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                println!("{}", diagnostic.format_colorful(&sources));
+            }
+            None
+        }
+    }
+}
+
+/// Prints the source code with every positional argument rewritten as a named
+/// argument, showing which parameter each one is assigned to.
+fn annotate(path: &str) {
+    let Some((analysis, _)) = load_analysis(path) else { return };
+    let hints = analysis::argument_hints(&analysis);
+    for (path, content) in analysis::annotate(&analysis.file_tree, &hints) {
+        if !content.trim().is_empty() {
+            println!("# {}.sr", path);
+            println!("{}", content);
+        }
+    }
+}
+
+/// Writes Markdown documentation for every file in the program to the given
+/// folder.
+fn doc(path: &str, output: &str) {
+    let Some((analysis, _)) = load_analysis(path) else { return };
+    for (page, content) in analysis::doc_pages(&analysis) {
+        let page = Path::new(output).join(page);
+        fs::create_dir_all(page.parent().unwrap()).unwrap();
+        fs::write(&page, content).unwrap();
+        println!("Wrote {}", page.display());
+    }
+}
+
+/// Prints the long-form explanation of a diagnostic code, or lists every code
+/// if none is given.
+fn explain(code: Option<&str>) {
+    let Some(code) = code else {
+        for code in ErrorCode::ALL {
+            println!("{}: {}", code.code(), code.title());
+        }
+        return;
+    };
+    match ErrorCode::from_code(code) {
+        Some(code) => print!("{}", code.explanation()),
+        None => {
+            println!(
+                "{} is not a diagnostic code. Run `scarlet explain` to list them.",
+                code
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_symbols(analysis: &Analysis, symbols: &[analysis::Symbol], depth: usize) {
+    for symbol in symbols {
+        let location = match symbol.label_position.or(symbol.position) {
+            Some(position) => analysis.sources.describe(position),
+            None => analysis::file_modules(analysis)
+                .into_iter()
+                .position(|(_, module)| module == symbol.item)
+                .and_then(|index| analysis.sources.file(index + 1))
+                .map(|file| file.path.clone())
+                .unwrap_or_default(),
+        };
+        let indent = "  ".repeat(depth);
+        let kind = symbol.kind.name();
+        println!("{}{} ({}) {}", indent, symbol.name, kind, location);
+        print_symbols(analysis, &symbol.children, depth + 1);
+    }
+}
+
+/// Prints every labeled item in the program, nested inside the structures,
+/// files and folders they are defined in.
+fn outline(path: &str) {
+    let Some((analysis, _)) = load_analysis(path) else { return };
+    print_symbols(&analysis, &analysis::outline(&analysis), 0);
+}
+
+/// Renames the item at the given path of labels, such as `module.item`, and
+/// updates every reference to it.
+fn rename(path: &str, item_path: &str, new_name: &str) {
+    let Some((analysis, sources)) = load_analysis(path) else { return };
+    let Some(item) = analysis.item_at_path(item_path) else {
+        println!("Nothing is labeled {}.", item_path);
+        return;
+    };
+    let rename = match analysis::rename(&analysis, item, new_name) {
+        Ok(rename) => rename,
+        Err(diagnostic) => {
+            println!("{}", diagnostic.format_colorful(&sources));
+            return;
+        }
+    };
+    for (index, file) in sources.files().iter().enumerate() {
+        let mut content = file.content.clone();
+        let mut edits = rename
+            .edits
+            .iter()
+            .filter(|edit| edit.position.file_index() == index + 1)
+            .collect::<Vec<_>>();
+        if edits.is_empty() {
+            continue;
+        }
+        edits.sort_by_key(|edit| edit.position.range().start);
+        for edit in edits.into_iter().rev() {
+            content.replace_range(edit.position.range(), &edit.new_text);
+        }
+        fs::write(&file.path, content).unwrap();
+        println!("Updated {}", file.path);
+    }
+    for file_rename in &rename.file_renames {
+        let from = format!("{}{}", path, file_rename.from);
+        let to = format!("{}{}", path, file_rename.to);
+        for extension in [".sr", ""] {
+            let from = format!("{}{}", from, extension);
+            if Path::new(&from).exists() {
+                let to = format!("{}{}", to, extension);
+                fs::rename(&from, &to).unwrap();
+                println!("Renamed {} to {}", from, to);
+            }
+        }
+    }
+}
+
+/// Prints what every name in the program refers to as JSON.
+fn tokens(path: &str) {
+    let Some((analysis, sources)) = load_analysis(path) else { return };
+    let mut tokens = Vec::new();
+    for token in analysis::semantic_tokens(&analysis) {
+        let Some(location) = sources.location(token.position) else { continue };
+        tokens.push(json!({
+            "file": location.path,
+            "line": location.line,
+            "column": location.column,
+            "text": sources.text(token.position),
+            "kind": token.kind.name(),
+            "declaration": token.declaration,
+            "language_item": token.language_item,
+        }));
+    }
+    println!("{}", serde_json::to_string_pretty(&tokens).unwrap());
+}
+
+/// Writes a generated file, or with `check` only makes sure the file there
+/// is already up to date. Returns whether it was.
+fn write_generated(path: &str, content: &str, check: bool) -> bool {
+    if !check {
+        fs::write(path, content).unwrap();
+        println!("Wrote {}", path);
+        true
+    } else if fs::read_to_string(path).ok().as_deref() == Some(content) {
+        println!("{} is up to date.", path);
+        true
+    } else {
+        println!("{} is out of date.", path);
+        false
+    }
+}
+
+/// Writes the TextMate grammar used by the VS Code extension to the given
+/// path. With `--check`, fails instead if the file there is out of date.
+fn textmate(path: &str, check: bool) {
+    let grammar = parser::textmate_grammar(&ParseContext::new());
+    if !write_generated(path, &grammar, check) {
+        std::process::exit(1);
+    }
+}
+
+/// Writes a tree-sitter grammar to the given folder, along with a test
+/// corpus made from each of the given example programs. With `--check`,
+/// fails instead if any of the files there are out of date.
+fn tree_sitter(folder: &str, examples: &[String], check: bool) {
+    let parse_context = ParseContext::new();
+    let grammar = parser::tree_sitter_grammar(&parse_context);
+    let corpus_folder = format!("{}/test/corpus", folder);
+    if !check {
+        fs::create_dir_all(&corpus_folder).unwrap();
+    }
+    let mut up_to_date = write_generated(&format!("{}/grammar.js", folder), &grammar, check);
+    for example in examples {
+        let Some(file_tree) = read_program(example) else {
+            std::process::exit(1);
+        };
+        let name = Path::new(example).file_name().unwrap().to_string_lossy();
+        let corpus = parser::tree_sitter_corpus(&name, &file_tree, &parse_context);
+        let path = format!("{}/{}.txt", corpus_folder, name);
+        up_to_date &= write_generated(&path, &corpus, check);
+    }
+    if !up_to_date {
+        std::process::exit(1);
+    }
+}
+
+fn compile(path: &str, undecidable_asserts: UndecidableAsserts) {
     println!("Reading source from {}", path);
 
     let time = Instant::now();
-    let file_tree = file_tree::read_root(&path).unwrap();
+    let Some(file_tree) = read_program(path) else { return };
+    let sources = SourceMap::new(path, &file_tree);
     println!("Read source in {:#?}", time.elapsed());
 
     let time = Instant::now();
@@ -27,7 +281,7 @@ pub(crate) fn entry() {
         Ok(root) => root,
         Err(diagnostics) => {
             for diagnostic in diagnostics {
-                println!("{}", diagnostic.format_colorful(&file_tree));
+                println!("{}", diagnostic.format_colorful(&sources));
             }
             return;
         }
@@ -39,7 +293,7 @@ pub(crate) fn entry() {
     let root = match create_root(&root, &parse_context, &mut env) {
         Ok(root) => root,
         Err(diagnostic) => {
-            println!("{}", diagnostic.format_colorful(&file_tree));
+            println!("{}", diagnostic.format_colorful(&sources));
             return;
         }
     };
@@ -51,14 +305,14 @@ pub(crate) fn entry() {
     println!("Completed process 1.");
     let env = env.processed();
     println!("Completed process 2.");
-    let result = env.processed();
+    let result = env.processed_with(undecidable_asserts);
     println!("Completed process 3.");
     let env = match result {
         Ok(env) => env,
         Err(errors) => {
             let num_errors = errors.len();
             for err in errors {
-                let error = err.format_colorful(&file_tree);
+                let error = err.format_colorful(&sources);
                 println!("{}", error);
             }
             println!("Compilation failed due to {} errors.", num_errors);
@@ -66,6 +320,25 @@ pub(crate) fn entry() {
         }
     };
 
-    println!("{:#?}", env);
+    let stats = env.stats();
+    println!(
+        "Merged {} of {} asserts into others checking the same types.",
+        stats.merged_asserts, stats.asserts
+    );
+    println!(
+        "Created {} items while processing, reusing existing identical items {} times.",
+        stats.interned_items, stats.reused_items
+    );
+    println!(
+        "Found {} of {} folded values in the cache.",
+        stats.fold_cache_hits,
+        stats.fold_cache_hits + stats.fold_cache_misses
+    );
+
+    for warning in env.warnings() {
+        println!("{}", warning.format_colorful(&sources));
+    }
+
+    println!("{:#?}", env.debug_with(&sources));
     println!("{:#?}", env[root]);
 }
@@ -8,6 +8,7 @@ use std::{
 
 use itertools::Itertools;
 use maplit::hashmap;
+use num::{BigInt, Zero};
 
 use crate::{
     definitions::{
@@ -15,6 +16,8 @@ use crate::{
         compound_type::{DCompoundType, Type, TypeId},
         constructor::DConstructor,
         identifier::DIdentifier,
+        integer::DInteger,
+        matchh::DMatch,
         member_access::{DMemberAccess, DUnresolvedMemberAccess},
         other::DOther,
         parameter::{DParameter, ParameterPtr},
@@ -23,10 +26,12 @@ use crate::{
             DPartiallyResolvedSubstitution, DSubstitution, DUnresolvedSubstitution,
             PartiallyResolvedTarget, Substitutions, UnresolvedTarget,
         },
+        type_of::DTypeOf,
     },
-    diagnostic::{Diagnostic, Position},
+    diagnostic::{Diagnostic, ErrorCode, Level, Position},
     item::query::{Query, QueryContext, RootQuery},
-    shared::OrderedMap,
+    shared::{OrderedMap, TripleBool},
+    source_map::SourceMap,
     util::PtrExtension,
 };
 
@@ -60,53 +65,68 @@ def_enum!(Def0 {
     DBuiltin,
     DCompoundType,
     DIdentifier,
+    DInteger,
+    DMatch,
     DUnresolvedMemberAccess,
     DParameter,
     DStructLiteral,
-    DUnresolvedSubstitution
+    DUnresolvedSubstitution,
+    DTypeOf
 });
 
 def_enum!(Def1 {
     DBuiltin,
     DCompoundType,
+    DInteger,
+    DMatch,
     DUnresolvedMemberAccess,
     DOther,
     DParameter,
     DStructLiteral,
-    DPartiallyResolvedSubstitution
+    DPartiallyResolvedSubstitution,
+    DTypeOf
 });
 
 def_enum!(Def2 {
     DBuiltin,
     DCompoundType,
     DConstructor,
+    DInteger,
+    DMatch,
     DUnresolvedMemberAccess,
     DOther,
     DParameter,
     DStructLiteral,
-    DPartiallyResolvedSubstitution
+    DPartiallyResolvedSubstitution,
+    DTypeOf
 });
 
 def_enum!(Def3 {
     DBuiltin,
     DCompoundType,
     DConstructor,
+    DInteger,
+    DMatch,
     DUnresolvedMemberAccess,
     DOther,
     DParameter,
     DStructLiteral,
-    DSubstitution
+    DSubstitution,
+    DTypeOf
 });
 
 def_enum!(Def4 {
     DBuiltin,
     DCompoundType,
     DConstructor,
+    DInteger,
+    DMatch,
     DMemberAccess,
     DOther,
     DParameter,
     DStructLiteral,
-    DSubstitution
+    DSubstitution,
+    DTypeOf
 });
 
 pub type Env0 = Environment<Def0>;
@@ -130,6 +150,8 @@ pub struct ItemMetadata {
     pub dependencies: HashSet<ParameterPtr>,
     pub r#type: Option<ItemId>,
     pub value: Option<ConstValue>,
+    /// The documentation written before the definition of the item.
+    pub doc: Option<String>,
 }
 
 impl ItemMetadata {
@@ -140,6 +162,7 @@ impl ItemMetadata {
             dependencies: HashSet::new(),
             r#type: None,
             value: None,
+            doc: None,
         }
     }
 }
@@ -155,7 +178,52 @@ pub enum AssertMessage {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Assert {
     condition_which_must_be_true: ItemId,
-    error_when_not: AssertMessage,
+    /// Every place which needed the condition to be true, all of which are
+    /// reported together if it is not.
+    error_when_not: Vec<AssertMessage>,
+}
+
+/// Identifies a definition by its structure, so that items created while
+/// processing which are defined identically can share one id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum InternKey {
+    Builtin(Builtin, Vec<ItemId>),
+    /// The components of a compound type, in order of their addresses.
+    CompoundType(Vec<TypeId>),
+    Constructor(ItemId),
+    Integer(BigInt),
+    Other(ItemId),
+    Substitution(ItemId, Vec<(ParameterPtr, ItemId)>, bool),
+    TypeOf(ItemId),
+}
+
+/// Counts of things the compiler did, for seeing how much work it saved.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// How many asserts were made, including ones merged into others.
+    pub asserts: usize,
+    /// How many asserts checked the same pair of types as an earlier one and
+    /// were merged into it.
+    pub merged_asserts: usize,
+    /// How many items were created while processing, not counting ones
+    /// which were identical to an existing item and shared its id instead.
+    pub interned_items: usize,
+    /// How many items shared the id of an identical existing item.
+    pub reused_items: usize,
+    /// How many times an item was folded with arguments it had already been
+    /// folded with.
+    pub fold_cache_hits: usize,
+    /// How many times an item was folded with new arguments.
+    pub fold_cache_misses: usize,
+}
+
+/// How to report an assert which could not be decided ahead of time because
+/// it depends on parameters which have no value yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UndecidableAsserts {
+    Allow,
+    Warn,
+    Deny,
 }
 
 #[derive(Clone)]
@@ -165,26 +233,62 @@ pub struct Environment<Def> {
     god_type: ItemId,
     all_items: Vec<(Option<Def>, ItemMetadata)>,
     asserts: Vec<Assert>,
+    /// The index in `asserts` of the assert for each dereferenced pair of
+    /// subtype and supertype.
+    assert_lookup: HashMap<(ItemId, ItemId), usize>,
+    /// Items created with `intern`, by their structure.
+    interned: HashMap<InternKey, ItemId>,
+    warnings: Vec<Diagnostic>,
+    stats: Stats,
+}
+
+/// Formats an environment for debugging, showing where each item was written
+/// in terms of the files of the program.
+pub struct EnvironmentDebug<'a, Def> {
+    env: &'a Environment<Def>,
+    sources: Option<&'a SourceMap>,
+}
+
+impl<Def> Environment<Def> {
+    pub fn debug_with<'a>(&'a self, sources: &'a SourceMap) -> EnvironmentDebug<'a, Def> {
+        EnvironmentDebug {
+            env: self,
+            sources: Some(sources),
+        }
+    }
 }
 
 impl<Def: Debug> Debug for Environment<Def> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Environment (Root {:?})", self.root)?;
+        let debug = EnvironmentDebug {
+            env: self,
+            sources: None,
+        };
+        Debug::fmt(&debug, f)
+    }
+}
+
+impl<'a, Def: Debug> Debug for EnvironmentDebug<'a, Def> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { env: this, sources } = self;
+        writeln!(f, "Environment (Root {:?})", this.root)?;
         writeln!(f)?;
         writeln!(f, "Language Items:")?;
-        for (key, value) in &self.language_items {
+        for (key, value) in &this.language_items {
             writeln!(f, "{:?} => {:#?}", key, value)?;
         }
         writeln!(f)?;
         writeln!(f, "Items:")?;
-        for (key, (item, meta)) in self.all_items.iter().enumerate() {
+        for (key, (item, meta)) in this.all_items.iter().enumerate() {
             write!(f, "I#{}", key)?;
             if let Some(parent) = meta.parent {
                 write!(f, " (Child of {:?})", parent)?;
             }
             write!(f, " ({} deps)", meta.dependencies.len())?;
-            if let Some(position) = meta.position {
-                write!(f, " ({})", position)?;
+            match (meta.position, sources) {
+                (Some(position), Some(sources)) => write!(f, " ({})", sources.describe(position))?,
+                (Some(position), None) => write!(f, " ({:?})", position)?,
+                (None, _) => (),
             }
             writeln!(f)?;
             if let Some(value) = &meta.value {
@@ -195,6 +299,8 @@ impl<Def: Debug> Debug for Environment<Def> {
                 writeln!(f, "Undefined")?;
             }
         }
+        writeln!(f)?;
+        writeln!(f, "{:#?}", this.stats)?;
         Ok(())
     }
 }
@@ -233,6 +339,10 @@ impl<Def: From<DStructLiteral>> Environment<Def> {
                 .map(|(_, meta)| (None, meta.clone()))
                 .collect(),
             asserts: source.asserts.clone(),
+            assert_lookup: source.assert_lookup.clone(),
+            interned: HashMap::new(),
+            warnings: source.warnings.clone(),
+            stats: source.stats.clone(),
         }
     }
 }
@@ -246,6 +356,17 @@ impl<Def> Environment<Def> {
         self.all_items[value.0].1.r#type.unwrap()
     }
 
+    /// Returns the type of an item, if it has been computed yet.
+    pub fn get_type(&self, item: ItemId) -> Option<ItemId> {
+        self.all_items[item.0].1.r#type
+    }
+
+    /// Returns whether the item exists in this environment, as opposed to
+    /// only existing in environments produced by later stages.
+    pub fn contains(&self, item: ItemId) -> bool {
+        item.0 < self.all_items.len()
+    }
+
     pub fn get_position(&self, item: ItemId) -> Option<Position> {
         self.all_items[item.0].1.position
     }
@@ -276,6 +397,14 @@ impl<Def> Environment<Def> {
         self.all_items[item.0].1.position
     }
 
+    pub fn set_doc(&mut self, item: ItemId, doc: String) {
+        self.all_items[item.0].1.doc = Some(doc);
+    }
+
+    pub fn get_doc(&self, item: ItemId) -> Option<&str> {
+        self.all_items[item.0].1.doc.as_deref()
+    }
+
     fn set_parent(&mut self, item: ItemId, parent: ItemId) {
         self.all_items[item.0].1.parent = Some(parent);
     }
@@ -300,10 +429,12 @@ impl<Def> Environment<Def> {
         definition: ItemId,
     ) -> Result<(), Diagnostic> {
         if self.language_items.contains_key(name) {
-            Err(Diagnostic::new().with_text_error(format!(
-                "Language item \"{}\" defined multiple times.",
-                name
-            )))
+            Err(Diagnostic::new()
+                .with_code(ErrorCode::DuplicateLanguageItem)
+                .with_text_error(format!(
+                    "Language item \"{}\" defined multiple times.",
+                    name
+                )))
         } else {
             self.language_items.insert(name.to_owned(), definition);
             Ok(())
@@ -313,10 +444,15 @@ impl<Def> Environment<Def> {
     pub fn get_language_item(&self, name: &str) -> Result<ItemId, Diagnostic> {
         self.language_items.get(name).copied().ok_or_else(|| {
             Diagnostic::new()
+                .with_code(ErrorCode::MissingLanguageItem)
                 .with_text_error(format!("The language item \"{}\" is not defined.", name))
         })
     }
 
+    pub fn is_language_item(&self, item: ItemId) -> bool {
+        self.language_items.values().any(|&language_item| language_item == item)
+    }
+
     pub fn set_root(&mut self, root: ItemId) {
         self.root = root
     }
@@ -332,6 +468,18 @@ impl<Def> Environment<Def> {
     pub fn is_defined(&self, item: ItemId) -> bool {
         self.all_items[item.0].0.is_some()
     }
+
+    pub fn all_item_ids(&self) -> impl Iterator<Item = ItemId> {
+        (0..self.all_items.len()).map(ItemId)
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
 }
 
 impl Environment<Def0> {
@@ -344,6 +492,10 @@ impl Environment<Def0> {
             god_type,
             all_items: vec![(None, ItemMetadata::new()), (None, ItemMetadata::new())],
             asserts: vec![],
+            assert_lookup: HashMap::new(),
+            interned: HashMap::new(),
+            warnings: vec![],
+            stats: Stats::default(),
         };
         this.define_item(root, DStructLiteral::new_module(vec![]));
         this.define_item(god_type, DBuiltin::god_type());
@@ -379,6 +531,14 @@ impl Environment<Def0> {
                 }
             }
             Def0::DIdentifier(_) => (),
+            Def0::DInteger(_) => (),
+            Def0::DMatch(r#match) => {
+                children.push(r#match.subject());
+                for case in r#match.cases() {
+                    children.push(case.binding());
+                    children.push(case.result());
+                }
+            }
             Def0::DUnresolvedMemberAccess(member) => children.push(member.base()),
             Def0::DParameter(param) => children.push(param.get_type()),
             Def0::DStructLiteral(r#struct) => {
@@ -392,12 +552,31 @@ impl Environment<Def0> {
                     children.push(*value);
                 }
             }
+            Def0::DTypeOf(_) => (),
         }
         for child in children {
             self.set_parent_and_propogate(child, parent);
         }
     }
 
+    /// Finds the item an identifier refers to when it is used inside the given
+    /// item, searching each enclosing structure in turn. The result of a case
+    /// of a `MATCH` can also refer to the value the case names.
+    pub fn lookup_identifier(&self, context: ItemId, ident: &str) -> Option<ItemId> {
+        if let Def0::DStructLiteral(lit) = &self[context] {
+            if let Some(field) = lit.get_field(ident) {
+                return Some(field);
+            }
+        }
+        let parent = self.parent(context)?;
+        if let Def0::DMatch(r#match) = &self[parent] {
+            if let Some(binding) = r#match.get_binding(context, ident) {
+                return Some(binding);
+            }
+        }
+        self.lookup_identifier(parent, ident)
+    }
+
     pub fn processed(&self) -> Env1 {
         let mut target = Environment::new_for_process_result(&self);
         Process0 {
@@ -438,16 +617,88 @@ impl Env2 {
         }
     }
 
+    /// The dependencies of the type of the given item, as far as they can be
+    /// known before types are computed.
+    pub fn get_type_of_deps(&self, item: ItemId) -> HashSet<ParameterPtr> {
+        let base_deps = self.get_deps(item).clone();
+        let mut deps = HashSet::new();
+        for dep in &base_deps {
+            deps.extend(self.get_deps(dep.original_type()).iter().cloned());
+        }
+        deps.extend(base_deps);
+        deps
+    }
+
+    /// Returns the label an item was given with `IS` in the structure or
+    /// `NEW_TYPE` that contains it, if any.
+    pub fn get_label(&self, item: ItemId) -> Option<&str> {
+        let parent = self.parent(item)?;
+        let fields = match &self[parent] {
+            Def2::DStructLiteral(r#struct) => r#struct.fields(),
+            Def2::DCompoundType(r#type) => match r#type.get_single_type() {
+                Some(r#type) if r#type.is_constructable_type() => {
+                    r#type.get_constructor_parameters()
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        fields
+            .iter()
+            .find(|(label, field)| *field == item && !label.is_empty())
+            .map(|(label, _)| &label[..])
+    }
+
+    /// Finds the item which defines the given parameter.
+    pub fn get_parameter_definition(&self, parameter: &ParameterPtr) -> Option<ItemId> {
+        (0..self.all_items.len())
+            .map(ItemId)
+            .find(|&item| match &self.all_items[item.0].0 {
+                Some(Def2::DParameter(p)) => &p.get_parameter_ptr() == parameter,
+                _ => false,
+            })
+    }
+
     pub fn processed(&self) -> Result<Env3, Vec<Diagnostic>> {
+        self.processed_with(UndecidableAsserts::Warn)
+    }
+
+    pub fn processed_with(
+        &self,
+        undecidable_asserts: UndecidableAsserts,
+    ) -> Result<Env3, Vec<Diagnostic>> {
+        self.processed_with_options(undecidable_asserts, true)
+    }
+
+    /// Like `processed_with`, but can fold every value from scratch to check
+    /// that caching folds does not change their results.
+    fn processed_with_options(
+        &self,
+        undecidable_asserts: UndecidableAsserts,
+        cache_folds: bool,
+    ) -> Result<Env3, Vec<Diagnostic>> {
         let mut target = Environment::new_for_process_result(&self);
-        let diagnostics = Process2 {
+        let mut process = Process2 {
             source: self,
             target: &mut target,
-        }
-        .process();
+            inferences: Vec::new(),
+            warnings: Vec::new(),
+            type_errors: Vec::new(),
+            undecidable_asserts,
+            missing_parameters: Vec::new(),
+            fold_cache: HashMap::new(),
+            cache_folds,
+        };
+        let mut diagnostics = process.process();
+        let mut warnings = process.warnings;
         if diagnostics.len() > 0 {
-            Err(diagnostics)
+            // Warnings from earlier stages are otherwise kept in the result.
+            let mut all = self.warnings.clone();
+            all.append(&mut warnings);
+            all.append(&mut diagnostics);
+            Err(all)
         } else {
+            target.warnings.append(&mut warnings);
             Ok(target)
         }
     }
@@ -459,16 +710,79 @@ impl Def3 {
             Def3::DBuiltin(d) => d.add_type_asserts(env),
             Def3::DCompoundType(..) => {}
             Def3::DConstructor(d) => d.add_type_asserts(env),
+            Def3::DInteger(_) => {}
+            Def3::DMatch(_) => {}
             Def3::DUnresolvedMemberAccess(_) => {}
             Def3::DParameter(d) => d.add_type_asserts(env),
             Def3::DSubstitution(d) => d.add_type_asserts(env),
             Def3::DStructLiteral(..) => {}
             Def3::DOther(_) => (),
+            Def3::DTypeOf(_) => (),
         }
     }
 }
 
+impl Def3 {
+    /// Items are compared after dereferencing them, so that references to
+    /// the same item written in different places are still identical.
+    fn intern_key(&self, env: &Env3) -> Option<InternKey> {
+        let item = |item: ItemId| env.dereference(item);
+        Some(match self {
+            Def3::DBuiltin(d) => InternKey::Builtin(
+                d.get_builtin(),
+                d.get_args().iter().copied().map(item).collect(),
+            ),
+            Def3::DCompoundType(d) => InternKey::CompoundType(
+                d.get_component_types()
+                    .keys()
+                    .cloned()
+                    .sorted_by_key(|id| match id {
+                        TypeId::GodType => 0,
+                        TypeId::Natural => 1,
+                        TypeId::Negative => 2,
+                        TypeId::UserType(id) => Rc::as_ptr(id) as usize,
+                    })
+                    .collect(),
+            ),
+            Def3::DConstructor(d) => InternKey::Constructor(item(d.r#type())),
+            Def3::DInteger(d) => InternKey::Integer(d.value().clone()),
+            Def3::DOther(d) => InternKey::Other(item(d.0)),
+            Def3::DSubstitution(d) => InternKey::Substitution(
+                item(d.base()),
+                d.substitutions()
+                    .iter()
+                    .map(|(param, value)| (param.ptr_clone(), item(*value)))
+                    .sorted_by_key(|(param, _)| (param.order(), param.original_type().0))
+                    .collect(),
+                d.do_asserts(),
+            ),
+            Def3::DTypeOf(d) => InternKey::TypeOf(item(d.base())),
+            Def3::DMatch(..)
+            | Def3::DParameter(..)
+            | Def3::DStructLiteral(..)
+            | Def3::DUnresolvedMemberAccess(..) => return None,
+        })
+    }
+}
+
 impl Env3 {
+    /// Creates an item with the given definition, or returns an existing
+    /// item which was created the same way with an identical definition.
+    pub fn intern(&mut self, definition: impl Into<Def3>) -> ItemId {
+        let definition = definition.into();
+        let Some(key) = definition.intern_key(self) else {
+            return self.new_defined_item(definition);
+        };
+        if let Some(&item) = self.interned.get(&key) {
+            self.stats.reused_items += 1;
+            return item;
+        }
+        self.stats.interned_items += 1;
+        let item = self.new_defined_item(definition);
+        self.interned.insert(key, item);
+        item
+    }
+
     pub fn dereference(&self, id: ItemId) -> ItemId {
         if let Def3::DOther(DOther(id)) = self[id] {
             self.dereference(id)
@@ -480,6 +794,12 @@ impl Env3 {
     pub fn assert_of_type(&mut self, item: ItemId, supertype: ItemId) {
         let original_item = item;
         let item = self.dereference(item);
+        if let Def3::DTypeOf(type_of) = &self[self.dereference(supertype)] {
+            if self.dereference(type_of.base()) == item {
+                // Anything is trivially of its own type.
+                return;
+            }
+        }
         let type_of_item = self.all_items[item.0].1.r#type.unwrap();
         self.assert_subtype(
             type_of_item,
@@ -498,11 +818,22 @@ impl Env3 {
             // This avoids an infinite loop of asserting that type is a type.
             return;
         }
+        self.stats.asserts += 1;
+        if let Some(&index) = self.assert_lookup.get(&(subtype, supertype)) {
+            self.stats.merged_asserts += 1;
+            let messages = &mut self.asserts[index].error_when_not;
+            if !messages.contains(&message) {
+                messages.push(message);
+            }
+            return;
+        }
         let def = DBuiltin::is_subtype_of(subtype, supertype);
-        let assert = self.new_defined_item(def);
+        let assert = self.intern(def);
+        self.assert_lookup
+            .insert((subtype, supertype), self.asserts.len());
         self.asserts.push(Assert {
             condition_which_must_be_true: assert,
-            error_when_not: message,
+            error_when_not: vec![message],
         });
     }
 }
@@ -530,17 +861,19 @@ impl<'a, 'b> Process0<'a, 'b> {
             Def0::DBuiltin(d) => self.target.define_item(item, d.clone()),
             Def0::DCompoundType(d) => self.target.define_item(item, d.clone()),
             Def0::DIdentifier(ident) => self.process_identifier(item, ident),
+            Def0::DInteger(d) => self.target.define_item(item, d.clone()),
+            Def0::DMatch(d) => self.target.define_item(item, d.clone()),
             Def0::DUnresolvedMemberAccess(d) => self.target.define_item(item, d.clone()),
             Def0::DParameter(d) => self.target.define_item(item, d.clone()),
             Def0::DStructLiteral(d) => self.target.define_item(item, d.clone()),
             Def0::DUnresolvedSubstitution(d) => self.process_unresolved_substitution(item, d),
+            Def0::DTypeOf(d) => self.target.define_item(item, d.clone()),
         }
         Ok(())
     }
 
     fn process_identifier(&mut self, this: ItemId, ident: &DIdentifier) {
-        let parent = self.source.parent(this).unwrap();
-        let target = self.lookup_identifier(parent, ident.identifier());
+        let target = self.source.lookup_identifier(this, ident.identifier());
         self.target.define_item(this, DOther(target.unwrap()));
     }
 
@@ -553,7 +886,7 @@ impl<'a, 'b> Process0<'a, 'b> {
                 let target = match target {
                     UnresolvedTarget::Positional => PartiallyResolvedTarget::Positional,
                     UnresolvedTarget::Named(name) => PartiallyResolvedTarget::Item(
-                        self.lookup_identifier(this, name).expect("TODO Nice error"),
+                        self.source.lookup_identifier(this, name).expect("TODO Nice error"),
                     ),
                 };
                 (target, *value)
@@ -562,19 +895,6 @@ impl<'a, 'b> Process0<'a, 'b> {
         self.target
             .define_item(this, DPartiallyResolvedSubstitution::new(base, subs));
     }
-
-    fn lookup_identifier(&self, context: ItemId, ident: &str) -> Option<ItemId> {
-        if let Def0::DStructLiteral(lit) = &self.source[context] {
-            if let Some(field) = lit.get_field(ident) {
-                return Some(field);
-            }
-        }
-        if let Some(parent) = self.source.parent(context) {
-            self.lookup_identifier(parent, ident)
-        } else {
-            None
-        }
-    }
 }
 
 struct Process1<'a, 'b> {
@@ -591,7 +911,8 @@ impl<'a, 'b> Process1<'a, 'b> {
         self.target.assert_all_defined();
         loop {
             let mut anything_changed = false;
-            for index in 0..self.source.all_items.len() {
+            // This includes items created while processing.
+            for index in 0..self.target.all_items.len() {
                 let id = ItemId(index);
                 anything_changed |= self.compute_deps(id);
             }
@@ -599,6 +920,55 @@ impl<'a, 'b> Process1<'a, 'b> {
                 break;
             }
         }
+        self.check_order_ties();
+    }
+
+    /// Warns about pairs of parameters of an item which have the same order
+    /// but are defined in different files, since which of them a positional
+    /// argument is assigned to would change if the files were reorganized.
+    /// Each pair is only reported for the first labeled item which has both.
+    fn check_order_ties(&mut self) {
+        let mut reported = HashSet::new();
+        for item in self.target.all_item_ids().collect_vec() {
+            if self.target.get_label(item).is_none() {
+                continue;
+            }
+            let mut deps = self.target.get_deps(item).iter().cloned().collect_vec();
+            deps.sort_by_key(|dep| dep.order());
+            for (index, a) in deps.iter().enumerate() {
+                for b in &deps[index + 1..] {
+                    let (a_order, b_order) = (a.order(), b.order());
+                    let tied = a_order.major_order() == b_order.major_order()
+                        && a_order.file_order() != b_order.file_order();
+                    if tied && reported.insert((a.ptr_clone(), b.ptr_clone())) {
+                        let warning = self.order_tie_warning(item, a, b);
+                        self.target.warnings.push(warning);
+                    }
+                }
+            }
+        }
+    }
+
+    fn order_tie_warning(&self, item: ItemId, a: &ParameterPtr, b: &ParameterPtr) -> Diagnostic {
+        let mut warning = Diagnostic::new()
+            .with_code(ErrorCode::ParameterOrderTie)
+            .with_text_warning(
+                "This has two parameters which are defined in different files but have the same \
+                 order. Which argument goes to which of them will change if those files are \
+                 reorganized:"
+                    .to_owned(),
+            )
+            .with_item_warning(item, &self.target);
+        for param in [a, b] {
+            if let Some(definition) = self.target.get_parameter_definition(param) {
+                let label = "this parameter has the same order as the other".to_owned();
+                warning = warning.with_item_secondary_label(definition, &self.target, label);
+            }
+        }
+        warning.with_help(
+            "Consider giving them distinct orders with ORDER(n) or using named arguments."
+                .to_owned(),
+        )
     }
 
     fn compute_deps(&mut self, item: ItemId) -> bool {
@@ -624,6 +994,24 @@ impl<'a, 'b> Process1<'a, 'b> {
                     }
                 }
             }
+            Def2::DInteger(_) => (),
+            Def2::DMatch(d) => {
+                deps.extend(self.target.get_deps(d.subject()).iter().cloned());
+                // The value each case names is given by the match itself.
+                for case in d.cases() {
+                    let Def2::DParameter(binding) = &self.target[case.binding()] else {
+                        unreachable!()
+                    };
+                    let binding = binding.get_parameter_ptr();
+                    deps.extend(
+                        self.target
+                            .get_deps(case.result())
+                            .iter()
+                            .filter(|&dep| dep != &binding)
+                            .cloned(),
+                    );
+                }
+            }
             Def2::DUnresolvedMemberAccess(d) => {
                 deps.extend(self.target.get_deps(d.base()).iter().cloned());
             }
@@ -643,27 +1031,34 @@ impl<'a, 'b> Process1<'a, 'b> {
                 }
             }
             Def2::DPartiallyResolvedSubstitution(d) => {
-                let base = self.target.get_deps(d.base()).iter().cloned().collect_vec();
-                let mut base = base;
-                base.sort_by_key(|p| p.order());
-                for (target, value) in d.substitutions() {
+                for (_, value) in d.substitutions() {
                     deps.extend(self.target.get_deps(*value).iter().cloned());
-                    match target {
-                        PartiallyResolvedTarget::Positional => {
-                            if base.len() > 0 {
-                                base.remove(0);
-                            }
-                        }
-                        &PartiallyResolvedTarget::Item(target) => {
-                            let Def2::DParameter(p) = &self.target[target] else { todo!("Nice error") };
-                            let target = p.get_parameter_ptr();
-                            if let Some(index) = base.iter().position(|x| x == &target) {
-                                base.remove(index);
-                            }
-                        }
+                }
+                // Errors are reported once the substitution is resolved.
+                if let Ok(assignment) = d.assign_arguments(&self.target) {
+                    for &candidate in assignment.inferred.iter().flat_map(|(_, c)| c) {
+                        deps.extend(self.target.get_type_of_deps(candidate));
                     }
+                    deps.extend(assignment.remaining);
+                    // The base can have parameters the arguments were not
+                    // assigned against, like the fields in `Type(args).new`.
+                    let assigned = assignment
+                        .explicit
+                        .iter()
+                        .map(|(param, _)| param)
+                        .chain(assignment.inferred.iter().map(|(param, _)| param))
+                        .collect_vec();
+                    deps.extend(
+                        self.target
+                            .get_deps(d.base())
+                            .iter()
+                            .filter(|param| !assigned.contains(param))
+                            .cloned(),
+                    );
                 }
-                deps.extend(base);
+            }
+            Def2::DTypeOf(d) => {
+                deps.extend(self.target.get_type_of_deps(d.base()));
             }
         };
         let original = &mut self.target.all_items[item.0].1.dependencies;
@@ -682,20 +1077,51 @@ impl<'a, 'b> Process1<'a, 'b> {
         match &self.source[item] {
             Def1::DBuiltin(d) => self.target.define_item(item, d.clone()),
             Def1::DCompoundType(d) => self.target.define_item(item, d.clone()),
+            Def1::DInteger(d) => self.target.define_item(item, d.clone()),
+            Def1::DMatch(d) => self.target.define_item(item, d.clone()),
             Def1::DOther(d) => self.target.define_item(item, d.clone()),
             Def1::DUnresolvedMemberAccess(d) => self.process_member_access(item, d),
             Def1::DParameter(d) => self.target.define_item(item, d.clone()),
             Def1::DStructLiteral(d) => self.target.define_item(item, d.clone()),
             Def1::DPartiallyResolvedSubstitution(d) => self.target.define_item(item, d.clone()),
+            Def1::DTypeOf(d) => self.target.define_item(item, d.clone()),
         }
         Ok(())
     }
 
+    /// If `base.member` is `new` on a compound type with arguments, returns
+    /// the type and the substitution giving it those arguments.
+    fn substituted_type(
+        &self,
+        base: ItemId,
+        member: &str,
+    ) -> Option<(ItemId, &'a DPartiallyResolvedSubstitution)> {
+        let Def1::DPartiallyResolvedSubstitution(sub) = &self.source[base] else {
+            return None;
+        };
+        let r#type = self.source.dereference(sub.base());
+        match (&self.source[r#type], member) {
+            (Def1::DCompoundType(_), "new") => Some((r#type, sub)),
+            _ => None,
+        }
+    }
+
     fn process_member_access(&mut self, this: ItemId, access: &DUnresolvedMemberAccess) {
         let base = self.source.dereference(access.base());
         if let Def1::DStructLiteral(module) = &self.source[base] {
             let item = module.get_field(access.member_name()).unwrap();
             self.target.define_item(this, DOther(item));
+        } else if let (Def1::DCompoundType(_), "new") = (&self.source[base], access.member_name()) {
+            self.target.define_item(this, DConstructor::new(base));
+        } else if let Some((r#type, sub)) = self.substituted_type(base, access.member_name()) {
+            // `Type(args).new` is the constructor of the type, given the same
+            // arguments.
+            let constructor = self.target.new_defined_item(DConstructor::new(r#type));
+            let substitutions = sub.substitutions().clone();
+            self.target.define_item(
+                this,
+                DPartiallyResolvedSubstitution::new_constructor(constructor, r#type, substitutions),
+            );
         } else {
             self.target.define_item(this, access.clone());
         }
@@ -705,8 +1131,46 @@ impl<'a, 'b> Process1<'a, 'b> {
 struct Process2<'a, 'b> {
     source: &'a Env2,
     target: &'b mut Env3,
+    inferences: Vec<Inference>,
+    warnings: Vec<Diagnostic>,
+    /// Errors found while computing the types of items.
+    type_errors: Vec<Diagnostic>,
+    undecidable_asserts: UndecidableAsserts,
+    /// Parameters which were needed to fold something but had no value.
+    missing_parameters: Vec<ParameterPtr>,
+    /// The results of folding each item with the arguments it depends on.
+    fold_cache: HashMap<ItemId, Vec<CachedFold>>,
+    cache_folds: bool,
 }
 
+/// The result of folding an item with some arguments, along with the
+/// parameters which were needed but had no value.
+struct CachedFold {
+    args: HashMap<ParameterPtr, ConstValue>,
+    value: Option<ConstValue>,
+    missing_parameters: Vec<ParameterPtr>,
+}
+
+/// Records a type argument which was inferred from more than one argument of
+/// a substitution, so that we can check that their types can be combined.
+struct Inference {
+    substitution: ItemId,
+    candidates: Vec<ItemId>,
+}
+
+/// A type or value computed ahead of time. Two of these are equal exactly
+/// when they are the same type or value, no matter which items they were
+/// computed from:
+/// - Types are equal when they have the same components, as compared by
+///   `DCompoundType`, and the same arguments.
+/// - Values are equal when their types are equal in the same way and their
+///   fields have the same values.
+/// - Integers are equal when they are the same number.
+///
+/// Parameters are compared by their order and original type, which no two
+/// parameters defined in different places share. `is_exactly`,
+/// `is_subtype_of` and the folding cache all use this equality, and interning
+/// identifies compound types the same way.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ConstValue {
     Type {
@@ -714,15 +1178,99 @@ pub enum ConstValue {
         arguments: HashMap<ParameterPtr, ConstValue>,
     },
     Value {
-        r#type: ItemId,
+        r#type: DCompoundType,
         subs: HashMap<ParameterPtr, ConstValue>,
     },
+    /// A value of `Int`, which is stored as a number rather than as fields so
+    /// that arithmetic can be done on it directly.
+    Integer(BigInt),
 }
 
 impl ConstValue {
+    /// Checks whether this is a type which is a subtype of the other one.
+    /// This is unknown if either of them is not a type.
+    pub fn is_subtype_of(&self, other: &ConstValue) -> TripleBool {
+        let (
+            ConstValue::Type {
+                r#type: a,
+                arguments: a_args,
+            },
+            ConstValue::Type {
+                r#type: b,
+                arguments: b_args,
+            },
+        ) = (self, other)
+        else {
+            return TripleBool::Unknown;
+        };
+        // If a really is a subtype of b, it will not have any additional parameters
+        // not in b.
+        let arguments = a_args.iter().map(|(param, value)| match b_args.get(param) {
+            Some(other) => value.is_argument_subtype_of(other),
+            None => TripleBool::False,
+        });
+        TripleBool::and(
+            std::iter::once(a.is_subtype_of(b))
+                .chain(arguments)
+                .collect(),
+        )
+    }
+
+    /// Checks whether a type with this as an argument can be used where the
+    /// same type with the other argument is needed. Arguments which are types
+    /// are only ever the types of fields, and narrowing the type of a field
+    /// narrows the type it is in, so they are compared as subtypes. Any other
+    /// argument has to be the same value.
+    pub fn is_argument_subtype_of(&self, other: &ConstValue) -> TripleBool {
+        match (self, other) {
+            (ConstValue::Type { .. }, ConstValue::Type { .. }) => self.is_subtype_of(other),
+            _ => (self == other).into(),
+        }
+    }
+
+    /// Combines two types into one which has the components of both. This is
+    /// only possible if both of them are types, and they do not give
+    /// different arguments to the same parameter.
+    pub fn union(self, other: ConstValue) -> Option<ConstValue> {
+        let (
+            ConstValue::Type {
+                r#type: a,
+                arguments: mut args,
+            },
+            ConstValue::Type {
+                r#type: b,
+                arguments: b_args,
+            },
+        ) = (self, other)
+        else {
+            return None;
+        };
+        for (param, value) in b_args {
+            match args.get(&param) {
+                Some(existing) if existing != &value => return None,
+                _ => {
+                    args.insert(param, value);
+                }
+            }
+        }
+        Some(ConstValue::Type {
+            r#type: a.union(&b),
+            arguments: args,
+        })
+    }
+
+    /// Returns the type this is a value of, or nothing if it is a type.
+    pub fn value_type(&self) -> Option<DCompoundType> {
+        match self {
+            ConstValue::Type { .. } => None,
+            ConstValue::Value { r#type, .. } => Some(r#type.clone()),
+            ConstValue::Integer(value) => Some(DCompoundType::of_integer(value)),
+        }
+    }
+
     pub fn into_item(self, env: &mut Env3) -> ItemId {
         let def = self.into_def(env);
-        env.new_defined_item(def)
+        env.intern(def)
     }
 
     pub fn into_def(self, env: &mut Env3) -> Def3 {
@@ -732,7 +1280,7 @@ impl ConstValue {
                 if arguments.len() == 0 {
                     base.into()
                 } else {
-                    let base = env.new_defined_item(base);
+                    let base = env.intern(base);
                     Def3::DSubstitution(DSubstitution::new(
                         base,
                         arguments
@@ -743,11 +1291,11 @@ impl ConstValue {
                 }
             }
             ConstValue::Value { r#type, subs } => {
+                let r#type = env.intern(r#type);
                 if subs.len() == 0 {
                     Def3::DConstructor(DConstructor::new(r#type))
                 } else {
-                    let constructor =
-                        env.new_defined_item(Def3::DConstructor(DConstructor::new(r#type)));
+                    let constructor = env.intern(Def3::DConstructor(DConstructor::new(r#type)));
                     Def3::DSubstitution(DSubstitution::new(
                         constructor,
                         subs.into_iter()
@@ -756,6 +1304,7 @@ impl ConstValue {
                     ))
                 }
             }
+            ConstValue::Integer(value) => Def3::DInteger(DInteger::new(value)),
         }
     }
 }
@@ -792,6 +1341,11 @@ impl<'a, 'b> Process2<'a, 'b> {
                 type_index += 1;
             }
         }
+        // Items whose types could not be computed are given `Type` instead,
+        // which would only cause more confusing errors later on.
+        if !self.type_errors.is_empty() {
+            return std::mem::take(&mut self.type_errors);
+        }
         let mut index = 0;
         while index < self.target.all_items.len() {
             let id = ItemId(index);
@@ -799,31 +1353,496 @@ impl<'a, 'b> Process2<'a, 'b> {
             index += 1;
         }
         self.target.assert_all_defined();
-        let mut errors = Vec::new();
-        for assert in &self.target.asserts {
+        let mut errors = self.check_inferences();
+        errors.append(&mut self.check_matches());
+        for assert in self.target.asserts.clone() {
             let condition = &self.target.all_items[assert.condition_which_must_be_true.0];
-            if let &Some(ConstValue::Value { r#type, .. }) = &condition.1.value {
-                if r#type == self.target.get_language_item("False").unwrap() {
-                    errors.push(self.make_error(assert));
+            if let Some(ConstValue::Value { r#type, .. }) = &condition.1.value {
+                if r#type == &self.bool_type(false) {
+                    errors.push(self.make_error(&assert));
                 }
-            } else {
-                errors.push(self.make_error(assert));
+                continue;
+            }
+            match self.undecidable_asserts {
+                UndecidableAsserts::Allow => (),
+                UndecidableAsserts::Warn => {
+                    let warning = self.make_undecidable_diagnostic(&assert, Level::Warning);
+                    self.warnings.push(warning);
+                }
+                UndecidableAsserts::Deny => {
+                    errors.push(self.make_undecidable_diagnostic(&assert, Level::Error))
+                }
+            }
+        }
+        errors
+    }
+
+    fn check_inferences(&mut self) -> Vec<Diagnostic> {
+        let mut errors = Vec::new();
+        for index in 0..self.inferences.len() {
+            let candidates = self.inferences[index].candidates.clone();
+            let mut types = Vec::new();
+            for candidate in candidates {
+                let r#type = self.get_type(candidate);
+                types.push(self.const_fold(r#type, HashMap::new()));
+            }
+            // Types which are not known yet might still be combined once they
+            // are.
+            let Some(types) = types.into_iter().collect::<Option<Vec<_>>>() else { continue };
+            let combined = types
+                .into_iter()
+                .map(Some)
+                .reduce(|a, b| a?.union(b?))
+                .flatten();
+            if combined.is_none() {
+                let inference = &self.inferences[index];
+                let mut error = Diagnostic::new()
+                    .with_code(ErrorCode::ConflictingInference)
+                    .with_text_error(
+                        "Cannot infer a type argument of this substitution because the arguments \
+                         it would be inferred from have types which cannot be combined:"
+                            .to_owned(),
+                    )
+                    .with_item_error(inference.substitution, &self.target);
+                for &candidate in &inference.candidates {
+                    let label = "the type argument could be inferred from this".to_owned();
+                    error = error.with_item_secondary_label(candidate, &self.target, label);
+                }
+                errors.push(
+                    error.with_help("Consider specifying the type argument explicitly.".to_owned()),
+                );
+            }
+        }
+        errors
+    }
+
+    /// Checks that every `MATCH` has a case for each type the value it matches
+    /// on can have, warning about cases which can never be chosen.
+    fn check_matches(&mut self) -> Vec<Diagnostic> {
+        let mut errors = Vec::new();
+        for item in self.source.all_item_ids() {
+            let Def3::DMatch(r#match) = &self.target[item] else { continue };
+            let r#match = r#match.clone();
+            let subject_type = self.get_type(r#match.subject());
+            let Some(possible) = self.type_ids(subject_type) else {
+                let error = Diagnostic::new()
+                    .with_code(ErrorCode::NonExhaustiveMatch)
+                    .with_text_error(
+                        "Cannot tell which cases this MATCH needs, because the type of the value \
+                         it matches on is not known ahead of time:"
+                            .to_owned(),
+                    )
+                    .with_item_primary_label(
+                        Level::Error,
+                        r#match.subject(),
+                        &self.target,
+                        "this can have any type".to_owned(),
+                    );
+                errors.push(error);
+                continue;
+            };
+            let possible = possible.into_iter().unique().collect_vec();
+            // Which case each type is matched by.
+            let mut matched: Vec<(TypeId, ItemId)> = Vec::new();
+            for case in r#match.cases() {
+                let Some(ids) = self.type_ids(case.pattern()) else {
+                    let error = Diagnostic::new()
+                        .with_code(ErrorCode::NonExhaustiveMatch)
+                        .with_text_error(
+                            "Cannot tell which values this case matches, because its type is not \
+                             known ahead of time:"
+                                .to_owned(),
+                        )
+                        .with_item_error(case.pattern(), &self.target);
+                    errors.push(error);
+                    continue;
+                };
+                let new = ids
+                    .iter()
+                    .filter(|id| possible.contains(id))
+                    .filter(|id| !matched.iter().any(|(other, _)| other == *id))
+                    .unique()
+                    .cloned()
+                    .collect_vec();
+                if new.is_empty() {
+                    let warning = self.unreachable_case_warning(case.pattern(), &ids, &matched);
+                    self.warnings.push(warning);
+                }
+                matched.extend(new.into_iter().map(|id| (id, case.pattern())));
+            }
+            let missing = possible
+                .iter()
+                .filter(|id| !matched.iter().any(|(other, _)| other == *id))
+                .map(|id| format!("`{}`", self.describe_type_id(id)))
+                .collect_vec();
+            if !missing.is_empty() {
+                let error = Diagnostic::new()
+                    .with_code(ErrorCode::NonExhaustiveMatch)
+                    .with_text_error(
+                        "This MATCH does not have a case for every type the value it matches on \
+                         can have:"
+                            .to_owned(),
+                    )
+                    .with_item_primary_label(
+                        Level::Error,
+                        r#match.subject(),
+                        &self.target,
+                        format!(
+                            "no case matches this when it has type {}",
+                            missing.join(" or ")
+                        ),
+                    )
+                    .with_help(format!(
+                        "Add a case for {}.",
+                        if missing.len() == 1 {
+                            "it"
+                        } else {
+                            "each of them"
+                        }
+                    ));
+                errors.push(error);
             }
         }
         errors
     }
 
+    fn unreachable_case_warning(
+        &self,
+        pattern: ItemId,
+        ids: &[TypeId],
+        matched: &[(TypeId, ItemId)],
+    ) -> Diagnostic {
+        let mut warning = Diagnostic::new()
+            .with_code(ErrorCode::UnreachableCase)
+            .with_text_warning("This case can never be chosen:".to_owned());
+        let earlier = matched
+            .iter()
+            .filter(|(id, _)| ids.contains(id))
+            .map(|&(_, pattern)| pattern)
+            .unique()
+            .collect_vec();
+        if earlier.is_empty() {
+            let label = "the value being matched on never has this type".to_owned();
+            return warning.with_item_primary_label(Level::Warning, pattern, &self.target, label);
+        }
+        let label = "every type this matches is matched by an earlier case".to_owned();
+        warning = warning.with_item_primary_label(Level::Warning, pattern, &self.target, label);
+        for earlier in earlier {
+            let label = "matched here first".to_owned();
+            warning = warning.with_item_secondary_label(earlier, &self.target, label);
+        }
+        warning
+    }
+
+    /// Returns the value an item was folded to, if it could be.
+    fn folded(&self, item: ItemId) -> Option<&ConstValue> {
+        let value = &self.target.all_items[item.0].1.value;
+        let item = self.target.dereference(item);
+        value
+            .as_ref()
+            .or_else(|| self.target.all_items[item.0].1.value.as_ref())
+    }
+
+    /// Names a component of a type after the item which defines it.
+    fn describe_type_id(&self, id: &TypeId) -> String {
+        match id {
+            TypeId::GodType => return "Type".to_owned(),
+            TypeId::Natural => return "Nat".to_owned(),
+            // Only `Int` has negative values, so they are named after it.
+            TypeId::Negative => return "Int".to_owned(),
+            TypeId::UserType(..) => (),
+        }
+        let is_only_component = |item: ItemId| {
+            let Def2::DCompoundType(r#type) = &self.source[self.source.dereference(item)] else {
+                return false;
+            };
+            let components = r#type.get_component_types();
+            components.len() == 1 && components.contains_key(id)
+        };
+        let labeled = self
+            .source
+            .all_item_ids()
+            .filter(|&item| self.source.get_label(item).is_some());
+        let mut definition = None;
+        let mut instance = None;
+        for item in labeled {
+            match &self.source[self.source.dereference(item)] {
+                Def2::DCompoundType(..) if is_only_component(item) => definition = Some(item),
+                Def2::DConstructor(con) if is_only_component(con.r#type()) => {
+                    instance = instance.or(Some(item))
+                }
+                _ => continue,
+            }
+            break;
+        }
+        // Types made with `UNIQUE` have no name, but their values do.
+        match (definition, instance) {
+            (Some(item), _) => self.source.get_label(item).unwrap().to_owned(),
+            (None, Some(item)) => format!("{}'s type", self.source.get_label(item).unwrap()),
+            (None, None) => "?".to_owned(),
+        }
+    }
+
+    fn describe_arguments(
+        &self,
+        base: String,
+        arguments: &HashMap<ParameterPtr, ConstValue>,
+    ) -> String {
+        if arguments.is_empty() {
+            return base;
+        }
+        let arguments = arguments
+            .iter()
+            .map(|(parameter, value)| {
+                let name = self.describe_parameter(parameter);
+                format!("{} IS {}", name, self.describe_const(value))
+            })
+            .sorted()
+            .join(" ");
+        format!("{}({})", base, arguments)
+    }
+
+    fn describe_parameter(&self, parameter: &ParameterPtr) -> String {
+        let Some(definition) = self.source.get_parameter_definition(parameter) else {
+            return "?".to_owned();
+        };
+        // Parameters used by builtins have the builtin as their parent, so
+        // look for a structure or a case of a `MATCH` which gives them a
+        // label.
+        let label = self.source.get_label(definition).or_else(|| {
+            self.source
+                .all_item_ids()
+                .find_map(|item| match &self.source[item] {
+                    Def2::DStructLiteral(r#struct) => r#struct
+                        .fields()
+                        .iter()
+                        .find(|(label, field)| {
+                            !label.is_empty() && self.source.dereference(*field) == definition
+                        })
+                        .map(|(label, _)| &label[..]),
+                    Def2::DMatch(r#match) => r#match
+                        .cases()
+                        .iter()
+                        .find(|case| !case.label().is_empty() && case.binding() == definition)
+                        .map(|case| case.label()),
+                    _ => None,
+                })
+        });
+        label.unwrap_or("?").to_owned()
+    }
+
+    /// Describes a value computed ahead of time, using the names of the items
+    /// which define it where there are any.
+    fn describe_const(&self, value: &ConstValue) -> String {
+        match value {
+            ConstValue::Type { r#type, arguments } => {
+                let named = self.source.all_item_ids().find(|&item| {
+                    self.target.all_items[item.0].1.value.as_ref() == Some(value)
+                        && self.source.get_label(item).is_some()
+                });
+                if let Some(item) = named {
+                    return self.source.get_label(item).unwrap().to_owned();
+                }
+                let mut components = r#type
+                    .get_component_types()
+                    .keys()
+                    .map(|id| self.describe_type_id(id))
+                    .sorted()
+                    .collect_vec();
+                let base = if r#type == &DCompoundType::int() {
+                    "Int".to_owned()
+                } else if components.len() == 1 {
+                    components.pop().unwrap()
+                } else {
+                    format!("Union({})", components.join(" "))
+                };
+                self.describe_arguments(base, arguments)
+            }
+            ConstValue::Value { r#type, subs } => {
+                let r#type = self.describe_const(&ConstValue::Type {
+                    r#type: r#type.clone(),
+                    arguments: HashMap::new(),
+                });
+                self.describe_arguments(format!("{}.new", r#type), subs)
+            }
+            ConstValue::Integer(value) => value.to_string(),
+        }
+    }
+
+    /// Explains why one type was found not to be a subtype of another, in the
+    /// same terms the `is_subtype_of` builtin checks it in.
+    fn subtype_failure_notes(
+        &self,
+        subtype: Option<&ConstValue>,
+        supertype: Option<&ConstValue>,
+    ) -> Vec<String> {
+        let (
+            Some(ConstValue::Type {
+                r#type: a,
+                arguments: a_args,
+            }),
+            Some(ConstValue::Type {
+                r#type: b,
+                arguments: b_args,
+            }),
+        ) = (subtype, supertype)
+        else {
+            return Vec::new();
+        };
+        let mut notes = Vec::new();
+        let expected = self.describe_const(supertype.unwrap());
+        let missing = a
+            .get_component_types()
+            .keys()
+            .filter(|id| !b.get_component_types().contains_key(id))
+            .map(|id| self.describe_type_id(id))
+            .sorted();
+        for name in missing {
+            notes.push(format!(
+                "`{}` is not one of the types in `{}`.",
+                name, expected
+            ));
+        }
+        let arguments = a_args
+            .iter()
+            .sorted_by_key(|(parameter, _)| self.describe_parameter(parameter));
+        for (parameter, argument) in arguments {
+            let name = self.describe_parameter(parameter);
+            let value = self.describe_const(argument);
+            match b_args.get(parameter) {
+                Some(other) if argument.is_argument_subtype_of(other) == TripleBool::True => (),
+                Some(other) => notes.push(format!(
+                    "`{}` is `{}` in the type of the expression, which does not fit `{}` in the \
+                     type it needs to have.",
+                    name,
+                    value,
+                    self.describe_const(other)
+                )),
+                None => notes.push(format!(
+                    "`{}` is `{}` in the type of the expression, but is not given in the type it \
+                     needs to have.",
+                    name, value
+                )),
+            }
+        }
+        notes
+    }
+
+    /// Describes an assert which could not be decided, along with the
+    /// parameters it would need values for.
+    fn make_undecidable_diagnostic(&mut self, assert: &Assert, level: Level) -> Diagnostic {
+        let condition = assert.condition_which_must_be_true;
+        self.missing_parameters.clear();
+        self.const_fold(condition, HashMap::new());
+        let missing_parameters = std::mem::take(&mut self.missing_parameters);
+        let text = if assert.error_when_not.len() == 1 {
+            "Could not check ahead of time that this expression has the type it needs to have:"
+        } else {
+            "Could not check ahead of time that these expressions have the type they need to have:"
+        };
+        let diagnostic = Diagnostic::new()
+            .with_code(ErrorCode::UndecidableAssert)
+            .with_text(level, text.to_owned());
+        let mut diagnostic = self.with_assert_sites(
+            diagnostic,
+            assert,
+            level,
+            "this may not have the type it needs to have",
+            "the type it needs to have comes from here",
+        );
+        let names = missing_parameters
+            .iter()
+            .map(|parameter| format!("`{}`", self.describe_parameter(parameter)))
+            .sorted()
+            .dedup()
+            .collect_vec();
+        if !names.is_empty() {
+            diagnostic = diagnostic.with_note(format!(
+                "The check depends on {}, which {} no value here.",
+                names.join(", "),
+                if names.len() == 1 { "has" } else { "have" }
+            ));
+        }
+        diagnostic.with_note(
+            "The types may still turn out to be compatible once the parameters it depends on are \
+             given values."
+                .to_owned(),
+        )
+    }
+
     fn make_error(&self, assert: &Assert) -> Diagnostic {
-        match &assert.error_when_not {
-            &AssertMessage::ItemTypeMustBeSubtype {
+        let Def3::DBuiltin(check) = &self.target[assert.condition_which_must_be_true] else {
+            unreachable!()
+        };
+        let subtype = self.folded(check.get_args()[0]);
+        let supertype = self.folded(check.get_args()[1]);
+        let describe = |value: Option<&ConstValue>| match value {
+            Some(value) => format!("type `{}`", self.describe_const(value)),
+            None => "a type which could not be computed".to_owned(),
+        };
+        let (actual, expected) = (describe(subtype), describe(supertype));
+        let (text, subject) = if assert.error_when_not.len() == 1 {
+            (
+                "This expression does not have the type it needs to have:",
+                "The expression",
+            )
+        } else {
+            (
+                "These expressions do not have the type they need to have:",
+                "Each expression",
+            )
+        };
+        let error = Diagnostic::new()
+            .with_code(ErrorCode::TypeMismatch)
+            .with_text_error(text.to_owned());
+        let mut error = self.with_assert_sites(
+            error,
+            assert,
+            Level::Error,
+            &format!("this has {}", actual),
+            &format!("it needs to have {} because of this", expected),
+        );
+        error = error.with_note(format!(
+            "{} has {}, but it needs to have {}.",
+            subject, actual, expected
+        ));
+        for note in self.subtype_failure_notes(subtype, supertype) {
+            error = error.with_note(note);
+        }
+        error
+    }
+
+    /// Labels every place which needed an assert to hold, along with where
+    /// the type each place needed came from.
+    fn with_assert_sites(
+        &self,
+        mut diagnostic: Diagnostic,
+        assert: &Assert,
+        level: Level,
+        label: &str,
+        supertype_label: &str,
+    ) -> Diagnostic {
+        let mut labeled_supertypes = Vec::new();
+        for message in &assert.error_when_not {
+            let &AssertMessage::ItemTypeMustBeSubtype {
                 type_of,
                 must_be_subtype_of,
-            } => Diagnostic::new()
-                .with_text_error("The following expression:".to_owned())
-                .with_item_error(type_of, &self.target)
-                .with_text_error("Must be of the following type:".to_owned())
-                .with_item_error(must_be_subtype_of, &self.target),
+            } = message;
+            diagnostic =
+                diagnostic.with_item_primary_label(level, type_of, &self.target, label.to_owned());
+            let Some(position) = self.target.get_position(must_be_subtype_of) else {
+                continue;
+            };
+            if !labeled_supertypes.contains(&position) {
+                labeled_supertypes.push(position);
+                diagnostic = diagnostic.with_item_secondary_label(
+                    must_be_subtype_of,
+                    &self.target,
+                    supertype_label.to_owned(),
+                );
+            }
         }
+        diagnostic
     }
 
     fn get_type(&mut self, item: ItemId) -> ItemId {
@@ -840,43 +1859,70 @@ impl<'a, 'b> Process2<'a, 'b> {
     fn type_of(&mut self, item: ItemId) -> ItemId {
         match &self.target[item] {
             Def3::DBuiltin(d) => match d.get_builtin() {
-                Builtin::IsExactly | Builtin::IsSubtypeOf => {
-                    self.target.get_language_item("Bool").unwrap()
-                }
+                Builtin::IsExactly
+                | Builtin::IsSubtypeOf
+                | Builtin::IsLessThan
+                | Builtin::IsAtMost => self.target.get_language_item("Bool").unwrap(),
                 Builtin::IfThenElse => d.get_args()[0],
                 Builtin::Union | Builtin::GodType => self.target.god_type(),
+                // The difference of two `Nat`s can still be negative.
+                Builtin::Subtract => self.target.intern(DCompoundType::int()),
+                Builtin::Add | Builtin::Multiply | Builtin::Divide | Builtin::Remainder => {
+                    let d = d.clone();
+                    self.arithmetic_type(&d)
+                }
             },
-            Def3::DCompoundType(_) => self.target.god_type(),
+            Def3::DCompoundType(_) | Def3::DTypeOf(_) => self.target.god_type(),
             Def3::DConstructor(d) => d.r#type(),
-            Def3::DUnresolvedMemberAccess(d) => {
+            Def3::DInteger(d) => {
+                let r#type = DCompoundType::of_integer(d.value());
+                self.target.intern(r#type)
+            }
+            Def3::DMatch(d) => {
                 let d = d.clone();
-                let base_type = self.get_type(d.base());
-                let Some(ConstValue::Type { r#type, arguments }) = self.const_fold(base_type, HashMap::new()) else { panic!() };
-                let Some(r#type) = r#type.get_single_type() else { panic!() };
-                let fields = r#type.get_constructor_parameters();
-                let field = fields
+                let subject_type = self.get_type(d.subject());
+                // Arguments of the type being matched on also apply to the
+                // types of the results, such as `Element` when matching on a
+                // `List(Bool)`.
+                let arguments = match self.const_fold(subject_type, HashMap::new()) {
+                    Some(ConstValue::Type { arguments, .. }) => arguments,
+                    _ => HashMap::new(),
+                };
+                let types = d
+                    .cases()
                     .iter()
-                    .find(|(name, _)| name == &d.member_name())
-                    .unwrap();
-                let base = self.get_type(field.1);
-                let base_deps = self.target.get_deps(base);
-                let filtered_arguments: Vec<_> = arguments
-                    .into_iter()
-                    .filter(|(param, _)| base_deps.contains(param))
+                    .map(|case| self.get_type(case.result()))
+                    .collect_vec();
+                let folded: Option<Vec<_>> = types
+                    .iter()
+                    .map(|&r#type| self.const_fold(r#type, arguments.clone()))
                     .collect();
-                if filtered_arguments.len() == 0 {
-                    base
+                let Some(folded) = folded else {
+                    // Types which are not known ahead of time are combined
+                    // once they are.
+                    return self.lazy_union(&types, arguments);
+                };
+                let union = folded
+                    .into_iter()
+                    .map(Some)
+                    .reduce(|a, b| a?.union(b?))
+                    .flatten();
+                if let Some(union) = union {
+                    union.into_item(&mut self.target)
                 } else {
-                    let mut realized_arguments = OrderedMap::new();
-                    for (param, arg) in filtered_arguments.into_iter() {
-                        realized_arguments
-                            .insert(param.ptr_clone(), arg.into_item(&mut self.target));
+                    let error = self.conflicting_case_types_error(&d);
+                    self.type_errors.push(error);
+                    self.target.god_type()
+                }
+            }
+            Def3::DUnresolvedMemberAccess(d) => {
+                let d = d.clone();
+                match self.member_type(item, &d) {
+                    Ok(r#type) => r#type,
+                    Err(error) => {
+                        self.type_errors.push(error);
+                        self.target.god_type()
                     }
-                    self.target
-                        .new_defined_item(Def3::DSubstitution(DSubstitution::new(
-                            base,
-                            realized_arguments,
-                        )))
                 }
             }
             Def3::DOther(d) => self.get_type(d.0),
@@ -893,7 +1939,7 @@ impl<'a, 'b> Process2<'a, 'b> {
                         declarations,
                     };
                     let r#type = DCompoundType::new_single(Rc::new(r#type));
-                    self.target.new_defined_item(r#type)
+                    self.target.intern(r#type)
                 } else {
                     todo!()
                 }
@@ -908,7 +1954,7 @@ impl<'a, 'b> Process2<'a, 'b> {
                     .collect();
                 if subs.len() > 0 {
                     let sub = DSubstitution::new(base, subs);
-                    self.target.new_defined_item(sub)
+                    self.target.intern(sub)
                 } else {
                     base
                 }
@@ -916,13 +1962,322 @@ impl<'a, 'b> Process2<'a, 'b> {
         }
     }
 
+    /// Adding, multiplying or dividing integers, or taking the remainder of
+    /// dividing them, only gives a negative result when one of them is
+    /// negative. So the result has the components of both of their types,
+    /// which makes it a `Nat` when both of them are.
+    fn arithmetic_type(&mut self, builtin: &DBuiltin) -> ItemId {
+        let (left_type, right_type) = (builtin.get_args()[0], builtin.get_args()[1]);
+        self.union_type(left_type, right_type)
+    }
+
+    /// Creates a `Union` of two types. Dependencies were computed before it
+    /// was created, so it needs to be given them for substituting arguments
+    /// into it. They only depend on its arguments, so an existing item
+    /// already has the same ones.
+    fn union_type(&mut self, a: ItemId, b: ItemId) -> ItemId {
+        let items_before = self.target.all_items.len();
+        let r#type = self.target.intern(DBuiltin::union(a, b));
+        if r#type.0 >= items_before {
+            let mut deps = self.target.get_deps(a).clone();
+            deps.extend(self.target.get_deps(b).iter().cloned());
+            self.target.all_items[r#type.0].1.dependencies = deps;
+        }
+        r#type
+    }
+
+    /// Combines the types of the cases of a `MATCH` into a `Union` which is
+    /// folded later, given the arguments of the type being matched on.
+    fn lazy_union(
+        &mut self,
+        types: &[ItemId],
+        arguments: HashMap<ParameterPtr, ConstValue>,
+    ) -> ItemId {
+        let mut union = types[0];
+        for &r#type in &types[1..] {
+            union = self.union_type(union, r#type);
+        }
+        let deps = self.target.get_deps(union).clone();
+        let substitutions: Substitutions = arguments
+            .into_iter()
+            .filter(|(param, _)| deps.contains(param))
+            .map(|(param, arg)| (param, arg.into_item(&mut self.target)))
+            .collect();
+        if substitutions.is_empty() {
+            union
+        } else {
+            self.target.intern(Def3::DSubstitution(DSubstitution::new(
+                union,
+                substitutions,
+            )))
+        }
+    }
+
+    fn conflicting_case_types_error(&self, r#match: &DMatch) -> Diagnostic {
+        let mut error = Diagnostic::new()
+            .with_code(ErrorCode::ConflictingCaseTypes)
+            .with_text_error(
+                "The cases of this MATCH have types which cannot be combined:".to_owned(),
+            )
+            .with_item_error(r#match.subject(), &self.target);
+        for case in r#match.cases() {
+            let label = "this is one of the results".to_owned();
+            error = error.with_item_secondary_label(case.result(), &self.target, label);
+        }
+        error.with_help(
+            "Make the results give the same arguments to the types they have in common.".to_owned(),
+        )
+    }
+
+    /// Returns the type of `True` or `False`.
+    fn bool_type(&self, value: bool) -> DCompoundType {
+        let name = if value { "True" } else { "False" };
+        let item = self.target.get_language_item(name).unwrap();
+        let Def3::DCompoundType(r#type) = &self.target[self.target.dereference(item)] else {
+            panic!("{} must be a type.", name)
+        };
+        r#type.clone()
+    }
+
+    fn bool_value(&self, value: bool) -> ConstValue {
+        ConstValue::Value {
+            r#type: self.bool_type(value),
+            subs: hashmap![],
+        }
+    }
+
+    /// Finds the type of the field a member access refers to, given the
+    /// arguments the type of its base has.
+    fn member_type(
+        &mut self,
+        item: ItemId,
+        d: &DUnresolvedMemberAccess,
+    ) -> Result<ItemId, Diagnostic> {
+        let base_type = self.get_type(d.base());
+        let (r#type, arguments, substitutions) = match self.const_fold(base_type, HashMap::new()) {
+            Some(ConstValue::Type { r#type, arguments }) => {
+                (r#type, arguments, Substitutions::new())
+            }
+            // The type of the base can depend on parameters which have no
+            // value here, like when it is a value a case of a `MATCH` names.
+            // The arguments it does have are used as they are.
+            _ => {
+                let Some((r#type, substitutions)) = self.unfolded_type(base_type) else {
+                    return Err(self.unknown_members_error(d.base()));
+                };
+                (r#type, HashMap::new(), substitutions)
+            }
+        };
+        let Some(single_type) = r#type.get_single_type() else {
+            return Err(self.member_of_union_error(d.base(), &r#type));
+        };
+        let field = match &**single_type {
+            Type::UserType { fields, .. } => {
+                fields.iter().find(|(name, _)| name == d.member_name())
+            }
+            _ => None,
+        };
+        let Some(field) = field else {
+            return Err(self.missing_member_error(item, d, &r#type));
+        };
+        let base = self.get_type(field.1);
+        let base_deps = self.target.get_deps(base);
+        let filtered_arguments: Vec<_> = arguments
+            .into_iter()
+            .filter(|(param, _)| base_deps.contains(param))
+            .collect();
+        let mut realized_arguments: Substitutions = substitutions
+            .into_iter()
+            .filter(|(param, _)| base_deps.contains(param))
+            .collect();
+        if filtered_arguments.is_empty() && realized_arguments.is_empty() {
+            Ok(base)
+        } else {
+            for (param, arg) in filtered_arguments.into_iter() {
+                realized_arguments.insert(param.ptr_clone(), arg.into_item(&mut self.target));
+            }
+            Ok(self.target.intern(Def3::DSubstitution(DSubstitution::new(
+                base,
+                realized_arguments,
+            ))))
+        }
+    }
+
+    fn unknown_members_error(&self, base: ItemId) -> Diagnostic {
+        Diagnostic::new()
+            .with_code(ErrorCode::InvalidMemberAccess)
+            .with_text_error(
+                "Cannot tell which members this value has, because its type is not known ahead of \
+                 time:"
+                    .to_owned(),
+            )
+            .with_item_primary_label(
+                Level::Error,
+                base,
+                &self.target,
+                "this can have any type".to_owned(),
+            )
+    }
+
+    fn member_of_union_error(&self, base: ItemId, r#type: &DCompoundType) -> Diagnostic {
+        let types = self.describe_components(r#type);
+        Diagnostic::new()
+            .with_code(ErrorCode::InvalidMemberAccess)
+            .with_text_error(
+                "Cannot access a member of this value, because it can have more than one type:"
+                    .to_owned(),
+            )
+            .with_item_primary_label(
+                Level::Error,
+                base,
+                &self.target,
+                format!("this can have type {}", types.join(" or ")),
+            )
+            .with_help(
+                "Use MATCH to handle each type separately, and access the member in the cases \
+                 which have it."
+                    .to_owned(),
+            )
+    }
+
+    fn missing_member_error(
+        &self,
+        item: ItemId,
+        d: &DUnresolvedMemberAccess,
+        r#type: &DCompoundType,
+    ) -> Diagnostic {
+        let types = self.describe_components(r#type);
+        Diagnostic::new()
+            .with_code(ErrorCode::InvalidMemberAccess)
+            .with_text_error(format!(
+                "This value does not have a member named `{}`:",
+                d.member_name()
+            ))
+            .with_item_primary_label(
+                Level::Error,
+                item,
+                &self.target,
+                format!("this is a value of type {}", types.join(" or ")),
+            )
+    }
+
+    fn describe_components(&self, r#type: &DCompoundType) -> Vec<String> {
+        r#type
+            .get_component_types()
+            .keys()
+            .map(|id| format!("`{}`", self.describe_type_id(id)))
+            .sorted()
+            .collect()
+    }
+
+    /// Finds the compound type an item refers to without computing its
+    /// arguments, along with the arguments which were given to it.
+    fn unfolded_type(&self, r#type: ItemId) -> Option<(DCompoundType, Substitutions)> {
+        let r#type = self.target.dereference(r#type);
+        match &self.target[r#type] {
+            Def3::DCompoundType(d) => Some((d.clone(), Substitutions::new())),
+            Def3::DSubstitution(d) => {
+                let (r#type, mut substitutions) = self.unfolded_type(d.base())?;
+                for (param, value) in d.substitutions() {
+                    if !substitutions.contains_key(param) {
+                        substitutions.insert(param.ptr_clone(), *value);
+                    }
+                }
+                Some((r#type, substitutions))
+            }
+            _ => None,
+        }
+    }
+
+    /// Lists the components a type has. Unlike folding the type, this works
+    /// even when its arguments depend on parameters which have no value here.
+    fn type_ids(&mut self, r#type: ItemId) -> Option<Vec<TypeId>> {
+        if let Some((r#type, _)) = self.unfolded_type(r#type) {
+            return Some(r#type.get_component_types().keys().cloned().collect());
+        }
+        let r#type = self.target.dereference(r#type);
+        if let Def3::DSubstitution(sub) = &self.target[r#type] {
+            let sub = sub.clone();
+            if let Def3::DBuiltin(union) = &self.target[self.target.dereference(sub.base())] {
+                if union.get_builtin() == Builtin::Union {
+                    let mut ids = Vec::new();
+                    for arg in union.get_args().to_vec() {
+                        let arg = match &self.target[self.target.dereference(arg)] {
+                            Def3::DParameter(param) => sub
+                                .substitutions()
+                                .get(&param.get_parameter_ptr())
+                                .copied()
+                                .unwrap_or(arg),
+                            _ => arg,
+                        };
+                        ids.extend(self.type_ids(arg)?);
+                    }
+                    return Some(ids);
+                }
+            }
+        }
+        match self.const_fold(r#type, HashMap::new())? {
+            ConstValue::Type { r#type, .. } => {
+                Some(r#type.get_component_types().keys().cloned().collect())
+            }
+            ConstValue::Value { .. } | ConstValue::Integer(..) => None,
+        }
+    }
+
+    /// Checks whether one type is a subtype of the other. This is unknown if
+    /// either of them depends on parameters which have no value in `args`.
+    fn is_subtype_of(
+        &mut self,
+        subtype: ItemId,
+        supertype: ItemId,
+        args: HashMap<ParameterPtr, ConstValue>,
+    ) -> TripleBool {
+        let subtype = self.target.dereference(subtype);
+        let supertype = self.target.dereference(supertype);
+        if subtype == supertype {
+            return TripleBool::True;
+        }
+        let (Some(a), Some(b)) = (
+            self.const_fold(subtype, args.clone()),
+            self.const_fold(supertype, args),
+        ) else {
+            return TripleBool::Unknown;
+        };
+        a.is_subtype_of(&b)
+    }
+
     fn const_fold(
         &mut self,
         item: ItemId,
         args: HashMap<ParameterPtr, ConstValue>,
     ) -> Option<ConstValue> {
         let no_args = args.is_empty();
-        let value = self.const_fold_inner(item, args);
+        let args = self.relevant_args(item, args);
+        let cached = self
+            .fold_cache
+            .get(&item)
+            .and_then(|results| results.iter().find(|result| result.args == args))
+            .map(|result| (result.value.clone(), result.missing_parameters.clone()));
+        let value = if let Some((value, missing_parameters)) = cached {
+            self.target.stats.fold_cache_hits += 1;
+            self.add_missing_parameters(missing_parameters);
+            value
+        } else {
+            self.target.stats.fold_cache_misses += 1;
+            let outer_missing_parameters = std::mem::take(&mut self.missing_parameters);
+            let value = self.const_fold_inner(item, args.clone());
+            let missing_parameters =
+                std::mem::replace(&mut self.missing_parameters, outer_missing_parameters);
+            self.add_missing_parameters(missing_parameters.clone());
+            if self.cache_folds {
+                self.fold_cache.entry(item).or_default().push(CachedFold {
+                    args,
+                    value: value.clone(),
+                    missing_parameters,
+                });
+            }
+            value
+        };
         if no_args {
             if let Some(value) = &value {
                 self.target.all_items[item.0].1.value = Some(value.clone());
@@ -931,6 +2286,30 @@ impl<'a, 'b> Process2<'a, 'b> {
         value
     }
 
+    fn add_missing_parameters(&mut self, parameters: Vec<ParameterPtr>) {
+        for parameter in parameters {
+            if !self.missing_parameters.contains(&parameter) {
+                self.missing_parameters.push(parameter);
+            }
+        }
+    }
+
+    /// Removes arguments which an item does not depend on, so that folding
+    /// it with different values for them can share a cached result. Only
+    /// items from the source have their dependencies computed, so arguments
+    /// to items created while processing are all kept.
+    fn relevant_args(
+        &self,
+        item: ItemId,
+        mut args: HashMap<ParameterPtr, ConstValue>,
+    ) -> HashMap<ParameterPtr, ConstValue> {
+        if item.0 < self.source.all_items.len() {
+            let deps = self.target.get_deps(item);
+            args.retain(|param, _| deps.contains(param));
+        }
+        args
+    }
+
     fn const_fold_inner(
         &mut self,
         item: ItemId,
@@ -945,76 +2324,26 @@ impl<'a, 'b> Process2<'a, 'b> {
                         self.const_fold(a, args.clone()),
                         self.const_fold(b, args.clone()),
                     ) {
-                        let return_type = if a == b {
-                            self.target.get_language_item("True").unwrap()
-                        } else {
-                            self.target.get_language_item("False").unwrap()
-                        };
-                        Some(ConstValue::Value {
-                            r#type: return_type,
-                            subs: hashmap![],
-                        })
+                        Some(self.bool_value(a == b))
                     } else {
                         None
                     }
                 }
                 Builtin::IsSubtypeOf => {
-                    let a = self.target.dereference(d.get_args()[0]);
-                    let b = self.target.dereference(d.get_args()[1]);
-                    if let (
-                        Some(ConstValue::Type {
-                            r#type: a,
-                            arguments: a_args,
-                        }),
-                        Some(ConstValue::Type {
-                            r#type: b,
-                            arguments: b_args,
-                        }),
-                    ) = (
-                        self.const_fold(a, args.clone()),
-                        self.const_fold(b, args.clone()),
-                    ) {
-                        let component_types_check_out = a
-                            .get_component_types()
-                            .keys()
-                            .all(|required_id| b.get_component_types().contains_key(required_id));
-                        // If a really is a subtype of b, it will not have any additional parameters
-                        // not in b.
-                        let arguments_check_out = a_args
-                            .iter()
-                            .all(|(param, value)| b_args.get(param) == Some(value));
-                        let return_type = if component_types_check_out && arguments_check_out {
-                            self.target.get_language_item("True").unwrap()
-                        } else {
-                            self.target.get_language_item("False").unwrap()
-                        };
-                        Some(ConstValue::Value {
-                            r#type: return_type,
-                            subs: hashmap![],
-                        })
-                    } else {
-                        None
+                    let (a, b) = (d.get_args()[0], d.get_args()[1]);
+                    match self.is_subtype_of(a, b, args) {
+                        TripleBool::True => Some(self.bool_value(true)),
+                        TripleBool::False => Some(self.bool_value(false)),
+                        TripleBool::Unknown => None,
                     }
                 }
                 Builtin::IfThenElse => {
-                    let true_type = self.target.get_language_item("True").unwrap();
-                    let false_type = self.target.get_language_item("False").unwrap();
                     let true_result = d.get_args()[2];
                     let false_result = d.get_args()[3];
                     let condition = self.const_fold(d.get_args()[1], args.clone());
-                    if condition
-                        == Some(ConstValue::Value {
-                            r#type: true_type,
-                            subs: hashmap![],
-                        })
-                    {
+                    if condition == Some(self.bool_value(true)) {
                         self.const_fold(true_result, args)
-                    } else if condition
-                        == Some(ConstValue::Value {
-                            r#type: false_type,
-                            subs: hashmap![],
-                        })
-                    {
+                    } else if condition == Some(self.bool_value(false)) {
                         self.const_fold(false_result, args)
                     } else {
                         None
@@ -1023,31 +2352,31 @@ impl<'a, 'b> Process2<'a, 'b> {
                 Builtin::Union => {
                     let a = self.target.dereference(d.get_args()[0]);
                     let b = self.target.dereference(d.get_args()[1]);
-                    if let (
-                        Some(ConstValue::Type {
-                            r#type: a,
-                            arguments: a_args,
-                        }),
-                        Some(ConstValue::Type {
-                            r#type: b,
-                            arguments: b_args,
-                        }),
-                    ) = (self.const_fold(a, args.clone()), self.const_fold(b, args))
-                    {
-                        let mut args = a_args;
-                        args.extend(b_args.into_iter());
-                        Some(ConstValue::Type {
-                            r#type: a.union(&b),
-                            arguments: args,
-                        })
-                    } else {
-                        None
-                    }
+                    let a = self.const_fold(a, args.clone())?;
+                    let b = self.const_fold(b, args)?;
+                    a.union(b)
                 }
                 Builtin::GodType => Some(ConstValue::Type {
                     r#type: DCompoundType::god_type(),
                     arguments: hashmap![],
                 }),
+                Builtin::Add
+                | Builtin::Subtract
+                | Builtin::Multiply
+                | Builtin::Divide
+                | Builtin::Remainder
+                | Builtin::IsLessThan
+                | Builtin::IsAtMost => {
+                    let builtin = d.get_builtin();
+                    let (left, right) = (d.get_args()[2], d.get_args()[3]);
+                    let ConstValue::Integer(left) = self.const_fold(left, args.clone())? else {
+                        return None;
+                    };
+                    let ConstValue::Integer(right) = self.const_fold(right, args)? else {
+                        return None;
+                    };
+                    self.integer_operation(builtin, left, right)
+                }
             },
             Def3::DCompoundType(d) => {
                 let params = d.parameters(&self.source);
@@ -1074,12 +2403,34 @@ impl<'a, 'b> Process2<'a, 'b> {
                 if subs.len() < deps.len() {
                     None
                 } else {
+                    let r#type = self.target.dereference(d.r#type());
+                    let Def3::DCompoundType(r#type) = &self.target[r#type] else {
+                        unreachable!()
+                    };
                     Some(ConstValue::Value {
-                        r#type: d.r#type(),
+                        r#type: r#type.clone(),
                         subs,
                     })
                 }
             }
+            Def3::DInteger(d) => Some(ConstValue::Integer(d.value().clone())),
+            Def3::DMatch(d) => {
+                let d = d.clone();
+                let subject = self.const_fold(d.subject(), args.clone())?;
+                let r#type = subject.value_type()?;
+                let id = r#type.get_component_types().keys().next().unwrap().clone();
+                for case in d.cases() {
+                    if self.type_ids(case.pattern())?.contains(&id) {
+                        let Def3::DParameter(binding) = &self.target[case.binding()] else {
+                            unreachable!()
+                        };
+                        let mut args = args;
+                        args.insert(binding.get_parameter_ptr(), subject);
+                        return self.const_fold(case.result(), args);
+                    }
+                }
+                None
+            }
             Def3::DUnresolvedMemberAccess(d) => {
                 let member_name = d.member_name().to_owned();
                 if let Some(ConstValue::Value {
@@ -1087,14 +2438,14 @@ impl<'a, 'b> Process2<'a, 'b> {
                     subs: values,
                 }) = self.const_fold(d.base(), args.clone())
                 {
-                    let Def3::DCompoundType(r#type) = &self.target[r#type] else { unreachable!() };
-                    assert_eq!(r#type.get_component_types().len(), 1);
+                    // Values only ever have the one type they were constructed
+                    // with, and members they do not have are reported when
+                    // computing types.
                     let (_, r#type) = r#type.get_component_types().iter().next().unwrap();
                     let field = r#type
                         .get_constructor_parameters()
                         .iter()
-                        .find(|x| x.0 == member_name)
-                        .unwrap();
+                        .find(|x| x.0 == member_name)?;
                     self.const_fold(field.1, values.clone())
                 } else {
                     None
@@ -1105,6 +2456,7 @@ impl<'a, 'b> Process2<'a, 'b> {
                 if let Some(value) = args.get(d.get_parameter()) {
                     Some(value.clone())
                 } else {
+                    self.add_missing_parameters(vec![d.get_parameter_ptr()]);
                     None
                 }
             }
@@ -1124,9 +2476,35 @@ impl<'a, 'b> Process2<'a, 'b> {
                 }
                 self.const_fold(d.base(), new_args)
             }
+            Def3::DTypeOf(d) => {
+                let base = d.base();
+                let r#type = self.get_type(base);
+                self.const_fold(r#type, args)
+            }
         }
     }
 
+    /// Does arithmetic or a comparison with two integers. Division rounds
+    /// towards zero, and dividing by zero has no result.
+    fn integer_operation(
+        &self,
+        builtin: Builtin,
+        left: BigInt,
+        right: BigInt,
+    ) -> Option<ConstValue> {
+        Some(match builtin {
+            Builtin::Add => ConstValue::Integer(left + right),
+            Builtin::Subtract => ConstValue::Integer(left - right),
+            Builtin::Multiply => ConstValue::Integer(left * right),
+            Builtin::Divide | Builtin::Remainder if right.is_zero() => return None,
+            Builtin::Divide => ConstValue::Integer(left / right),
+            Builtin::Remainder => ConstValue::Integer(left % right),
+            Builtin::IsLessThan => self.bool_value(left < right),
+            Builtin::IsAtMost => self.bool_value(left <= right),
+            _ => unreachable!("{} does not operate on integers.", builtin.name()),
+        })
+    }
+
     fn process_item(&mut self, item: ItemId) -> Result<(), Diagnostic> {
         if self.target.is_defined(item) {
             return Ok(());
@@ -1135,6 +2513,8 @@ impl<'a, 'b> Process2<'a, 'b> {
             Def2::DBuiltin(d) => self.target.define_item(item, d.clone()),
             Def2::DCompoundType(d) => self.target.define_item(item, d.clone()),
             Def2::DConstructor(d) => self.target.define_item(item, d.clone()),
+            Def2::DInteger(d) => self.target.define_item(item, d.clone()),
+            Def2::DMatch(d) => self.target.define_item(item, d.clone()),
             Def2::DOther(d) => self.target.define_item(item, d.clone()),
             Def2::DUnresolvedMemberAccess(d) => self.target.define_item(item, d.clone()),
             Def2::DParameter(d) => self.target.define_item(item, d.clone()),
@@ -1142,6 +2522,7 @@ impl<'a, 'b> Process2<'a, 'b> {
             Def2::DPartiallyResolvedSubstitution(d) => {
                 return self.process_partially_resolved_substitution(item, d)
             }
+            Def2::DTypeOf(d) => self.target.define_item(item, d.clone()),
         }
         Ok(())
     }
@@ -1152,35 +2533,55 @@ impl<'a, 'b> Process2<'a, 'b> {
         sub: &DPartiallyResolvedSubstitution,
     ) -> Result<(), Diagnostic> {
         let base = sub.base();
+        let assignment = sub.assign_arguments(&self.source)?;
         let mut substitutions = Substitutions::new();
-        let mut deps = self.source.get_deps(sub.base()).clone();
-        for (target, value) in sub.substitutions() {
-            let target = match target {
-                PartiallyResolvedTarget::Positional => {
-                    let min_dep = deps.iter().min_by_key(|dep| dep.order());
-                    if let Some(dep) = min_dep {
-                        dep.ptr_clone()
-                    } else {
-                        return Err(Diagnostic::new()
-                            .with_text_error(
-                                "A substitution requires fewer arguments. All parameters have \
-                                 been substituted, leaving this argument with no corresponding \
-                                 parameter:"
-                                    .to_owned(),
-                            )
-                            .with_item_error(*value, &self.source));
-                    }
-                }
-                &PartiallyResolvedTarget::Item(target) => {
-                    let Def2::DParameter(p) = &self.source[target] else { todo!("Nice error") };
-                    p.get_parameter_ptr()
-                }
-            };
-            deps.remove(&target);
-            substitutions.insert(target, *value);
+        for (target, value) in assignment.explicit {
+            substitutions.insert(target, value);
+        }
+        for (target, candidates) in assignment.inferred {
+            let inferred = self.inferred_type(this, &candidates);
+            if candidates.len() > 1 {
+                self.inferences.push(Inference {
+                    substitution: this,
+                    candidates: candidates.clone(),
+                });
+            }
+            substitutions.insert(target, inferred);
         }
         self.target
             .define_item(this, DSubstitution::new(base, substitutions));
         Ok(())
     }
+
+    /// Creates the type argument inferred from the given arguments of a
+    /// substitution, which is the union of all of their types.
+    fn inferred_type(&mut self, sub: ItemId, candidates: &[ItemId]) -> ItemId {
+        let mut inferred: Option<ItemId> = None;
+        for &candidate in candidates {
+            let r#type = self.target.new_defined_item(DTypeOf::new(candidate));
+            self.target.all_items[r#type.0].1.dependencies =
+                self.source.get_type_of_deps(candidate);
+            if let Some(position) = self.source.get_position(candidate) {
+                self.target.set_position(r#type, position);
+            }
+            let Some(previous) = inferred else {
+                inferred = Some(r#type);
+                continue;
+            };
+            let union = self
+                .target
+                .new_defined_item(DBuiltin::union(previous, r#type));
+            let mut deps = self.target.get_deps(previous).clone();
+            deps.extend(self.target.get_deps(r#type).iter().cloned());
+            self.target.all_items[union.0].1.dependencies = deps;
+            if let Some(position) = self.source.get_position(sub) {
+                self.target.set_position(union, position);
+            }
+            inferred = Some(union);
+        }
+        inferred.unwrap()
+    }
 }
+
+#[cfg(test)]
+pub(crate) mod tests;
@@ -0,0 +1,874 @@
+use super::*;
+use crate::{
+    analysis::Analysis,
+    file_tree::{self, FileNode},
+};
+
+/// Defines the language items most programs need.
+pub(crate) const PRELUDE: &str = "
+Type IS BUILTIN(Type)
+Subtype0 IS ANY Type AS_LANGUAGE_ITEM(Subtype0)
+Subtype1 IS ANY Type AS_LANGUAGE_ITEM(Subtype1)
+Union IS BUILTIN(Union)
+True IS NEW_TYPE() AS_LANGUAGE_ITEM(True)
+False IS NEW_TYPE() AS_LANGUAGE_ITEM(False)
+true IS True.new AS_LANGUAGE_ITEM(true)
+false IS False.new AS_LANGUAGE_ITEM(false)
+Bool IS Union(True False) AS_LANGUAGE_ITEM(Bool)
+Result IS ANY Type AS_LANGUAGE_ITEM(Result)
+condition IS ANY Bool AS_LANGUAGE_ITEM(condition)
+true_result IS ANY Result AS_LANGUAGE_ITEM(true_result)
+false_result IS ANY Result AS_LANGUAGE_ITEM(false_result)
+if_then_else IS BUILTIN(if_then_else)
+";
+
+/// Runs a program written after the prelude through every stage of the
+/// compiler.
+pub(crate) fn analyze(source: &str) -> Analysis {
+    analyze_files(source, &[])
+}
+
+/// Like `analyze`, but with other files next to the root file.
+pub(crate) fn analyze_files(source: &str, children: &[(&str, &str)]) -> Analysis {
+    let file_tree = FileNode {
+        self_content: format!("{}\n{}", PRELUDE, source),
+        children: Vec::new(),
+    };
+    analyze_tree(file_tree, children)
+}
+
+/// Runs one of the example programs, with other files added next to its root
+/// file.
+fn analyze_example(name: &str, children: &[(&str, &str)]) -> Analysis {
+    let path = format!("{}/{}/{}", env!("CARGO_MANIFEST_DIR"), name, name);
+    analyze_tree(file_tree::read_root(path).unwrap(), children)
+}
+
+fn analyze_tree(mut file_tree: FileNode, children: &[(&str, &str)]) -> Analysis {
+    for &(name, content) in children {
+        let file = FileNode {
+            self_content: content.to_owned(),
+            children: Vec::new(),
+        };
+        file_tree.children.push((name.to_owned(), file));
+    }
+    let sources = SourceMap::new("test", &file_tree);
+    match Analysis::new(file_tree, sources.clone()) {
+        Ok(analysis) => analysis,
+        Err(diagnostics) => panic!("{}", describe_diagnostics(&diagnostics, &sources)),
+    }
+}
+
+fn describe_diagnostics(diagnostics: &[Diagnostic], sources: &SourceMap) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.format_plain(sources))
+        .join("\n")
+}
+
+/// Returns the environment of a program which compiled without errors.
+fn env3(analysis: &Analysis) -> &Env3 {
+    match &analysis.env3 {
+        Ok(env) => env,
+        Err(diagnostics) => panic!("{}", describe_diagnostics(diagnostics, &analysis.sources)),
+    }
+}
+
+/// Returns the codes of the diagnostics of a program which did not compile,
+/// including its warnings.
+fn diagnostic_codes(analysis: &Analysis) -> Vec<ErrorCode> {
+    let Err(diagnostics) = &analysis.env3 else { panic!("The program compiled.") };
+    diagnostics.iter().filter_map(Diagnostic::code).collect()
+}
+
+/// Returns what the item at the given path was folded to, if it could be.
+fn folded(analysis: &Analysis, path: &str) -> Option<ConstValue> {
+    let env = env3(analysis);
+    let item = analysis.item_at_path(path).expect(path);
+    [item, env.dereference(item)]
+        .iter()
+        .find_map(|&item| env.all_items[item.0].1.value.clone())
+}
+
+/// Returns what the item at the given path was folded to.
+fn value(analysis: &Analysis, path: &str) -> ConstValue {
+    folded(analysis, path).expect(path)
+}
+
+#[test]
+fn infers_union_of_differing_argument_types() {
+    let analysis = analyze(
+        "
+        c IS ANY Bool
+        picked IS if_then_else(c true false)
+        chosen IS picked(false)
+        ",
+    );
+    assert_eq!(value(&analysis, "chosen"), value(&analysis, "false"));
+}
+
+#[test]
+fn type_parameters_never_take_positional_arguments() {
+    let analysis = analyze(
+        "
+        partial IS if_then_else(Result IS Bool)
+        applied IS partial(true false true)
+        ",
+    );
+    assert_eq!(value(&analysis, "applied"), value(&analysis, "false"));
+}
+
+#[test]
+fn conflicting_type_arguments_are_ambiguous() {
+    let analysis = analyze(
+        "
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        c IS ANY Bool
+        main IS if_then_else(c Box(True).new(true) Box(False).new(false))
+        ",
+    );
+    let codes = diagnostic_codes(&analysis);
+    assert!(codes.contains(&ErrorCode::ConflictingInference));
+    assert!(!codes.contains(&ErrorCode::TypeMismatch));
+}
+
+#[test]
+fn named_arguments_must_name_parameters() {
+    let analysis = analyze("main IS if_then_else(True IS true)");
+    let codes = diagnostic_codes(&analysis);
+    assert_eq!(codes, vec![ErrorCode::NamedArgumentNotParameter]);
+}
+
+const COMPARISON: &str = "
+Comparee IS ANY Type AS_LANGUAGE_ITEM(Comparee)
+Comparand IS ANY Type AS_LANGUAGE_ITEM(Comparand)
+comparee IS ANY Comparee AS_LANGUAGE_ITEM(comparee)
+comparand IS ANY Comparand AS_LANGUAGE_ITEM(comparand)
+is_exactly IS BUILTIN(is_exactly)
+";
+
+#[test]
+fn identical_values_are_equal_and_interned_together() {
+    let mut analysis = analyze(&format!(
+        "{}
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        first IS Box(Bool)
+        second IS Box(Bool)
+        other IS Box(True)
+        first_value IS Box(True).new(true)
+        second_value IS Box(True).new(true)
+        same_type IS is_exactly(first second)
+        same_value IS is_exactly(first_value second_value)
+        ",
+        COMPARISON
+    ));
+    let first = value(&analysis, "first");
+    let second = value(&analysis, "second");
+    let other = value(&analysis, "other");
+    let first_value = value(&analysis, "first_value");
+    let second_value = value(&analysis, "second_value");
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+    assert_eq!(first_value, second_value);
+    assert_eq!(value(&analysis, "same_type"), value(&analysis, "true"));
+    assert_eq!(value(&analysis, "same_value"), value(&analysis, "true"));
+
+    let env = analysis.env3.as_mut().unwrap();
+    let first = first.into_item(env);
+    assert_eq!(first, second.into_item(env));
+    assert_ne!(first, other.into_item(env));
+    assert_eq!(first_value.into_item(env), second_value.into_item(env));
+    // Types which differ only in how their fields were written down are
+    // still the same type.
+    let type_id = Rc::new(());
+    let without_fields = env.intern(user_type(&type_id, &[]));
+    assert_eq!(without_fields, env.intern(user_type(&type_id, &["field"])));
+}
+
+#[test]
+fn type_arguments_are_compared_as_subtypes() {
+    let analysis = analyze_example("mini", &[("test", "TrueTemplate IS TemplateType(True)")]);
+    let narrow = value(&analysis, "test.TrueTemplate");
+    let wide = value(&analysis, "BoolTemplate");
+    assert_eq!(narrow.is_subtype_of(&wide), TripleBool::True);
+    assert_eq!(wide.is_subtype_of(&narrow), TripleBool::False);
+}
+
+#[test]
+fn wider_type_arguments_do_not_fit_narrower_ones() {
+    let analysis = analyze_example(
+        "mini",
+        &[(
+            "test",
+            "
+            narrow_slot IS ANY TemplateType(True)
+            main IS narrow_slot(TemplateType(Bool).new(true))
+            ",
+        )],
+    );
+    assert_eq!(diagnostic_codes(&analysis), vec![ErrorCode::TypeMismatch]);
+}
+
+const FLAGGED: &str = "
+flag IS ANY Bool
+Flagged IS NEW_TYPE(field IS ANY if_then_else(flag True False))
+TrueFlagged IS Flagged(true)
+FalseFlagged IS Flagged(false)
+";
+
+#[test]
+fn other_arguments_must_be_equal() {
+    let analysis = analyze(&format!(
+        "{}
+        slot IS ANY Flagged(true)
+        main IS slot(TrueFlagged.new(true))
+        ",
+        FLAGGED
+    ));
+    let r#true = value(&analysis, "TrueFlagged");
+    let r#false = value(&analysis, "FalseFlagged");
+    assert_eq!(r#true.is_subtype_of(&r#true), TripleBool::True);
+    assert_eq!(r#true.is_subtype_of(&r#false), TripleBool::False);
+    assert_eq!(r#false.is_subtype_of(&r#true), TripleBool::False);
+
+    let analysis = analyze(&format!(
+        "{}
+        slot IS ANY FalseFlagged
+        main IS slot(TrueFlagged.new(true))
+        ",
+        FLAGGED
+    ));
+    assert_eq!(diagnostic_codes(&analysis), vec![ErrorCode::TypeMismatch]);
+}
+
+#[test]
+fn warns_once_about_parameters_tied_across_files() {
+    let analysis = analyze_files(
+        "u IS ANY Bool",
+        &[(
+            "second",
+            "
+            w IS ANY Bool
+            g IS if_then_else(u w w)
+            h IS if_then_else(u g w)
+            ",
+        )],
+    );
+    let codes = env3(&analysis)
+        .warnings()
+        .iter()
+        .filter_map(Diagnostic::code)
+        .collect_vec();
+    assert_eq!(codes, vec![ErrorCode::ParameterOrderTie]);
+}
+
+/// Checks that an equality relation is reflexive, symmetric and transitive
+/// over the given values.
+fn assert_equivalence<T: PartialEq + Clone + Debug>(values: &[T]) {
+    for a in values {
+        assert!(*a == a.clone(), "{:?} is not equal to itself", a);
+        for b in values {
+            let (forwards, backwards) = (a == b, b == a);
+            assert_eq!(forwards, backwards, "{:?} and {:?}", a, b);
+            for c in values {
+                if a == b && b == c {
+                    assert!(a == c, "{:?} == {:?} == {:?}", a, b, c);
+                }
+            }
+        }
+    }
+    let equal_pairs = values
+        .iter()
+        .tuple_combinations()
+        .filter(|(a, b)| a == b)
+        .count();
+    assert!(equal_pairs > 0, "No two values are equal.");
+}
+
+fn user_type(type_id: &Rc<()>, fields: &[&str]) -> DCompoundType {
+    let fields = fields
+        .iter()
+        .map(|&name| (name.to_owned(), ItemId(0)))
+        .collect();
+    DCompoundType::new_single(Rc::new(Type::UserType {
+        type_id: TypeId::UserType(Rc::clone(type_id)),
+        fields,
+    }))
+}
+
+/// Types built in different ways, some of which are the same type.
+fn compound_types() -> Vec<DCompoundType> {
+    let (a, b) = (Rc::new(()), Rc::new(()));
+    vec![
+        DCompoundType::god_type(),
+        DCompoundType::nat(),
+        DCompoundType::int(),
+        DCompoundType::of_integer(&BigInt::from(-1)),
+        DCompoundType::nat().union(&DCompoundType::of_integer(&BigInt::from(-1))),
+        user_type(&a, &[]),
+        user_type(&a, &["contents"]),
+        user_type(&b, &[]),
+        user_type(&a, &[]).union(&user_type(&b, &[])),
+        user_type(&b, &[]).union(&user_type(&a, &[])),
+        user_type(&a, &[]).union(&DCompoundType::nat()),
+    ]
+}
+
+#[test]
+fn compound_type_equality_is_an_equivalence() {
+    assert_equivalence(&compound_types());
+}
+
+fn arguments(arguments: Vec<(&ParameterPtr, ConstValue)>) -> HashMap<ParameterPtr, ConstValue> {
+    arguments
+        .into_iter()
+        .map(|(param, arg)| (Rc::clone(param), arg))
+        .collect()
+}
+
+fn type_value(r#type: &DCompoundType, args: Vec<(&ParameterPtr, ConstValue)>) -> ConstValue {
+    ConstValue::Type {
+        r#type: r#type.clone(),
+        arguments: arguments(args),
+    }
+}
+
+fn constructed_value(r#type: &DCompoundType, subs: Vec<(&ParameterPtr, ConstValue)>) -> ConstValue {
+    ConstValue::Value {
+        r#type: r#type.clone(),
+        subs: arguments(subs),
+    }
+}
+
+fn integer(value: i32) -> ConstValue {
+    ConstValue::Integer(BigInt::from(value))
+}
+
+#[test]
+fn const_value_equality_is_an_equivalence() {
+    let parameter = |minor_order| {
+        let position = Position::new(0, minor_order..minor_order + 1);
+        DParameter::new(0, position, ItemId(0)).get_parameter_ptr()
+    };
+    // The same parameter, created twice.
+    let (p, p_again, q) = (parameter(0), parameter(0), parameter(1));
+    let types = compound_types();
+    let (nat, int, a, a_with_field) = (&types[1], &types[2], &types[5], &types[6]);
+    let (b, a_or_b, b_or_a) = (&types[7], &types[8], &types[9]);
+    assert_equivalence(&[
+        integer(0),
+        integer(0),
+        integer(-1),
+        type_value(nat, vec![]),
+        type_value(int, vec![]),
+        type_value(a, vec![]),
+        type_value(a_with_field, vec![]),
+        type_value(a_or_b, vec![]),
+        type_value(b_or_a, vec![]),
+        type_value(a, vec![(&p, type_value(nat, vec![]))]),
+        type_value(a, vec![(&p_again, type_value(nat, vec![]))]),
+        type_value(a, vec![(&q, type_value(nat, vec![]))]),
+        type_value(a, vec![(&p, type_value(int, vec![]))]),
+        type_value(a, vec![(&p, integer(0))]),
+        type_value(a, vec![(&p, type_value(nat, vec![])), (&q, integer(0))]),
+        type_value(a, vec![(&q, integer(0)), (&p, type_value(nat, vec![]))]),
+        constructed_value(a, vec![]),
+        constructed_value(b, vec![]),
+        constructed_value(a, vec![(&p, integer(0))]),
+        constructed_value(a_with_field, vec![(&p, integer(0))]),
+        constructed_value(a, vec![(&p, integer(-1))]),
+        constructed_value(a, vec![(&p, constructed_value(b, vec![]))]),
+        constructed_value(a, vec![(&p_again, constructed_value(b, vec![]))]),
+    ]);
+}
+
+const LIST: &str = "
+Nil IS NEW_TYPE()
+Element IS ANY Type
+List IS NEW_TYPE(head IS ANY Element tail IS ANY Union(List Nil))
+short IS List(Bool).new(true Nil.new)
+long IS List(Bool).new(false short)
+";
+
+#[test]
+fn recursive_types_fold_through_members() {
+    let analysis = analyze(&format!(
+        "{}
+        rest IS long.tail
+        second IS MATCH(long.tail
+            list IS List => list.head
+            Nil => false
+        )
+        Loop IS NEW_TYPE(next IS ANY Loop)
+        start IS ANY Loop
+        later IS start.next.next
+        ",
+        LIST
+    ));
+    assert_eq!(value(&analysis, "rest"), value(&analysis, "short"));
+    assert_eq!(value(&analysis, "second"), value(&analysis, "true"));
+}
+
+#[test]
+fn members_of_unions_need_a_match() {
+    let analysis = analyze(&format!("{}\nsecond IS long.tail.head", LIST));
+    assert_eq!(
+        diagnostic_codes(&analysis),
+        vec![ErrorCode::InvalidMemberAccess]
+    );
+}
+
+#[test]
+fn members_must_exist() {
+    let analysis = analyze(&format!("{}\nmissing IS long.size", LIST));
+    assert_eq!(
+        diagnostic_codes(&analysis),
+        vec![ErrorCode::InvalidMemberAccess]
+    );
+}
+
+const NUMBERS: &str = "
+Nat IS BUILTIN(Nat)
+Int IS BUILTIN(Int)
+Left IS ANY Type AS_LANGUAGE_ITEM(Left)
+Right IS ANY Type AS_LANGUAGE_ITEM(Right)
+left IS ANY Left AS_LANGUAGE_ITEM(left)
+right IS ANY Right AS_LANGUAGE_ITEM(right)
+add IS BUILTIN(add)
+subtract IS BUILTIN(subtract)
+multiply IS BUILTIN(multiply)
+divide IS BUILTIN(divide)
+remainder IS BUILTIN(remainder)
+is_less_than IS BUILTIN(is_less_than)
+is_at_most IS BUILTIN(is_at_most)
+";
+
+#[test]
+fn integer_literals_are_read_as_numbers() {
+    let analysis = analyze(&format!(
+        "{}
+        padded IS 007
+        huge IS 123456789012345678901234567890
+        ",
+        NUMBERS
+    ));
+    assert_eq!(value(&analysis, "padded"), integer(7));
+    let huge = "123456789012345678901234567890".parse().unwrap();
+    assert_eq!(value(&analysis, "huge"), ConstValue::Integer(huge));
+}
+
+#[test]
+fn arithmetic_is_folded() {
+    let analysis = analyze(&format!(
+        "{}
+        sum IS add(2 3)
+        difference IS subtract(2 5)
+        product IS multiply(difference 4)
+        quotient IS divide(subtract(0 7) 2)
+        leftover IS remainder(subtract(0 7) 2)
+        less IS is_less_than(difference 0)
+        at_most IS is_at_most(sum 4)
+        ",
+        NUMBERS
+    ));
+    assert_eq!(value(&analysis, "sum"), integer(5));
+    assert_eq!(value(&analysis, "difference"), integer(-3));
+    assert_eq!(value(&analysis, "product"), integer(-12));
+    // Division rounds towards zero.
+    assert_eq!(value(&analysis, "quotient"), integer(-3));
+    assert_eq!(value(&analysis, "leftover"), integer(-1));
+    assert_eq!(value(&analysis, "less"), value(&analysis, "true"));
+    assert_eq!(value(&analysis, "at_most"), value(&analysis, "false"));
+}
+
+#[test]
+fn dividing_by_zero_has_no_value() {
+    let analysis = analyze(&format!(
+        "{}
+        quotient IS divide(1 0)
+        leftover IS remainder(1 0)
+        ",
+        NUMBERS
+    ));
+    assert_eq!(folded(&analysis, "quotient"), None);
+    assert_eq!(folded(&analysis, "leftover"), None);
+}
+
+#[test]
+fn naturals_are_integers() {
+    let analysis = analyze(&format!(
+        "{}
+        slot IS ANY Int
+        main IS slot(5)
+        ",
+        NUMBERS
+    ));
+    let (nat, int) = (value(&analysis, "Nat"), value(&analysis, "Int"));
+    assert_eq!(nat.is_subtype_of(&int), TripleBool::True);
+    assert_eq!(int.is_subtype_of(&nat), TripleBool::False);
+
+    let analysis = analyze(&format!(
+        "{}
+        slot IS ANY Nat
+        main IS slot(subtract(2 5))
+        ",
+        NUMBERS
+    ));
+    assert_eq!(diagnostic_codes(&analysis), vec![ErrorCode::TypeMismatch]);
+}
+
+#[test]
+fn mutually_recursive_types_fold_and_check() {
+    let source = "
+    Nil IS NEW_TYPE()
+    Tree IS NEW_TYPE(label IS ANY Bool children IS ANY Union(Forest Nil))
+    Forest IS NEW_TYPE(first IS ANY Tree rest IS ANY Union(Forest Nil))
+    leaf IS Tree.new(true Nil.new)
+    forest IS Forest.new(leaf Nil.new)
+    root IS Tree.new(false forest)
+    first_label IS MATCH(root.children
+        children IS Forest => children.first.label
+        Nil => false
+    )
+    ";
+    let analysis = analyze(source);
+    let env = env3(&analysis);
+    for name in ["Tree", "Forest", "root"] {
+        let item = analysis.item_at_path(name).unwrap();
+        assert_eq!(env.get_deps(item).len(), 0, "{} has dependencies", name);
+    }
+    assert_eq!(value(&analysis, "first_label"), value(&analysis, "true"));
+
+    let analysis = analyze(&format!("{}\nwrong IS Tree.new(true leaf)", source));
+    assert_eq!(diagnostic_codes(&analysis), vec![ErrorCode::TypeMismatch]);
+}
+
+#[test]
+fn peano_numbers_fold_and_check() {
+    let source = "
+    Zero IS NEW_TYPE()
+    Successor IS NEW_TYPE(of IS ANY PeanoNumber)
+    PeanoNumber IS Union(Zero Successor)
+    one IS Successor.new(Zero.new)
+    two IS Successor.new(one)
+    input IS ANY PeanoNumber
+    predecessor IS MATCH(input
+        Zero => Zero.new
+        n IS Successor => n.of
+    )
+    main IS predecessor(two)
+    ";
+    let analysis = analyze(source);
+    let env = env3(&analysis);
+    let predecessor = analysis.item_at_path("predecessor").unwrap();
+    assert_eq!(env.get_deps(predecessor).len(), 1);
+    assert_eq!(value(&analysis, "main"), value(&analysis, "one"));
+
+    let analysis = analyze(&format!("{}\nwrong IS Successor.new(true)", source));
+    assert_eq!(diagnostic_codes(&analysis), vec![ErrorCode::TypeMismatch]);
+}
+
+#[test]
+fn caching_folds_does_not_change_their_results() {
+    let analysis = analyze_example(
+        "full",
+        &[(
+            "test",
+            "
+            wrapped IS sum(Byte.new(200) Byte.new(100))
+            overflow IS carry(Byte.new(200) Byte.new(100))
+            no_overflow IS carry(Byte.new(2) Byte.new(1))
+            byte_130 IS Byte.new(130)
+            byte_44 IS Byte.new(44)
+            ",
+        )],
+    );
+    let cached = env3(&analysis);
+    let uncached = analysis
+        .env2
+        .processed_with_options(UndecidableAsserts::Warn, false)
+        .unwrap();
+    assert!(cached.stats.fold_cache_hits > 0);
+    assert_eq!(uncached.stats.fold_cache_hits, 0);
+    for index in 0..analysis.env2.all_items.len() {
+        assert_eq!(
+            cached.all_items[index].1.value,
+            uncached.all_items[index].1.value,
+            "{:?} was folded differently",
+            ItemId(index)
+        );
+    }
+    assert_eq!(
+        value(&analysis, "some_number"),
+        value(&analysis, "test.byte_130")
+    );
+    assert_eq!(
+        value(&analysis, "test.wrapped"),
+        value(&analysis, "test.byte_44")
+    );
+    assert_eq!(value(&analysis, "test.overflow"), value(&analysis, "true"));
+    assert_eq!(
+        value(&analysis, "test.no_overflow"),
+        value(&analysis, "false")
+    );
+}
+
+#[test]
+fn identical_definitions_share_an_item() {
+    let mut analysis = analyze(
+        "
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        first IS Box(True).new(true)
+        second IS Box(True).new(true)
+        ",
+    );
+    let env = env3(&analysis);
+    let type_of = |name| {
+        let item = analysis.item_at_path(name).unwrap();
+        env.dereference(env.get_type(item).unwrap())
+    };
+    assert_eq!(type_of("first"), type_of("second"));
+
+    let env = analysis.env3.as_mut().unwrap();
+    let reused_items = env.stats.reused_items;
+    let five = env.intern(DInteger::new(BigInt::from(5)));
+    assert_eq!(five, env.intern(DInteger::new(BigInt::from(5))));
+    assert_ne!(five, env.intern(DInteger::new(BigInt::from(6))));
+    let union = env.intern(DBuiltin::union(five, five));
+    assert_eq!(union, env.intern(DBuiltin::union(five, five)));
+    assert_eq!(env.stats.reused_items, reused_items + 2);
+}
+
+#[test]
+fn arithmetic_builtins_share_a_result_type() {
+    let analysis = analyze(&format!("{}\nsum IS add(2 3)", NUMBERS));
+    let env = env3(&analysis);
+    let type_of = |name| {
+        let item = analysis.item_at_path(name).unwrap();
+        env.dereference(env.get_type(item).unwrap())
+    };
+    for name in ["multiply", "divide", "remainder"] {
+        assert_eq!(type_of("add"), type_of(name), "{}", name);
+    }
+    assert_eq!(value(&analysis, "sum"), integer(5));
+}
+
+/// Returns the codes of the diagnostics of a program which could not be read.
+fn parse_error_codes(source: &str) -> Vec<ErrorCode> {
+    let file_tree = FileNode {
+        self_content: format!("{}\n{}", PRELUDE, source),
+        children: Vec::new(),
+    };
+    let sources = SourceMap::new("test", &file_tree);
+    let Err(diagnostics) = Analysis::new(file_tree, sources) else { panic!("The program was read.") };
+    diagnostics.iter().filter_map(Diagnostic::code).collect()
+}
+
+#[test]
+fn matches_need_cases() {
+    for source in ["main IS MATCH(true True)", "main IS MATCH(true)"] {
+        assert_eq!(
+            parse_error_codes(source),
+            vec![ErrorCode::MalformedMatch],
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn matches_need_a_case_for_every_type() {
+    let analysis = analyze(
+        "
+        c IS ANY Bool
+        main IS MATCH(c True => false)
+        ",
+    );
+    assert_eq!(
+        diagnostic_codes(&analysis),
+        vec![ErrorCode::NonExhaustiveMatch]
+    );
+}
+
+#[test]
+fn warns_about_cases_which_are_never_chosen() {
+    let analysis = analyze(
+        "
+        c IS ANY Bool
+        main IS MATCH(c
+            Bool => c
+            True => true
+        )
+        ",
+    );
+    let codes = env3(&analysis)
+        .warnings()
+        .iter()
+        .filter_map(Diagnostic::code)
+        .collect_vec();
+    assert_eq!(codes, vec![ErrorCode::UnreachableCase]);
+}
+
+#[test]
+fn bound_values_have_the_type_of_their_case() {
+    let analysis = analyze(&format!(
+        "{}
+        list IS ANY Union(List(Bool) Nil)
+        first IS MATCH(list
+            l IS List(Bool) => l.head
+            Nil => false
+        )
+        applied IS first(list IS short)
+        ",
+        LIST
+    ));
+    assert!(env3(&analysis).warnings().is_empty());
+    assert_eq!(value(&analysis, "applied"), value(&analysis, "true"));
+
+    let analysis = analyze(&format!(
+        "{}
+        list IS ANY Union(List(Bool) Nil)
+        first IS MATCH(list
+            l IS List(Bool) => list.head
+            Nil => false
+        )
+        ",
+        LIST
+    ));
+    assert_eq!(
+        diagnostic_codes(&analysis),
+        vec![ErrorCode::InvalidMemberAccess]
+    );
+}
+
+#[test]
+fn match_types_are_combined_once_known() {
+    let analysis = analyze(
+        "
+        Element IS ANY Type
+        element IS ANY Element
+        c IS ANY Bool
+        pick IS MATCH(c
+            True => element
+            False => false
+        )
+        Zero IS NEW_TYPE()
+        zero IS Zero.new
+        main IS pick(c IS true Element IS Zero element IS zero)
+        other IS pick(c IS false Element IS Zero element IS zero)
+        ",
+    );
+    assert!(env3(&analysis).warnings().is_empty());
+    assert_eq!(value(&analysis, "main"), value(&analysis, "zero"));
+    assert_eq!(value(&analysis, "other"), value(&analysis, "false"));
+}
+
+#[test]
+fn match_types_must_be_combinable() {
+    let analysis = analyze(
+        "
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        c IS ANY Bool
+        main IS MATCH(c
+            True => Box(True).new(true)
+            False => Box(False).new(false)
+        )
+        ",
+    );
+    assert_eq!(
+        diagnostic_codes(&analysis),
+        vec![ErrorCode::ConflictingCaseTypes]
+    );
+}
+
+#[test]
+fn undecidable_asserts_are_reported_as_configured() {
+    let analysis = analyze(
+        "
+        Element IS ANY Type
+        element IS ANY Element
+        flag IS ANY Bool
+        main IS flag(element)
+        ",
+    );
+    // They are warnings by default.
+    assert_eq!(env3(&analysis).warnings().len(), 1);
+    let sources = &analysis.sources;
+    let processed = |mode| analysis.env2.processed_with(mode);
+
+    let env = processed(UndecidableAsserts::Allow).unwrap();
+    assert!(env.warnings().is_empty());
+
+    let env = processed(UndecidableAsserts::Warn).unwrap();
+    assert_eq!(env.warnings().len(), 1);
+    let warning = env.warnings()[0].format_plain(sources);
+    assert!(warning.starts_with("WARN[E0019]"), "{}", warning);
+
+    let Err(errors) = processed(UndecidableAsserts::Deny) else { panic!("The program compiled.") };
+    assert_eq!(errors.len(), 1);
+    let error = errors[0].format_plain(sources);
+    assert!(error.starts_with("ERROR[E0019]"), "{}", error);
+}
+
+#[test]
+fn unique_values_are_only_equal_to_themselves() {
+    let analysis = analyze(&format!(
+        "{}
+        red IS UNIQUE
+        green IS UNIQUE
+        same IS red = red
+        different IS red = green
+        x IS ANYTHING
+        is_red IS x = red
+        red_is_red IS is_red(red)
+        green_is_red IS is_red(green)
+        bool_is_red IS is_red(true)
+        ",
+        COMPARISON
+    ));
+    assert_ne!(value(&analysis, "red"), value(&analysis, "green"));
+    assert_eq!(value(&analysis, "same"), value(&analysis, "true"));
+    assert_eq!(value(&analysis, "different"), value(&analysis, "false"));
+    assert_eq!(folded(&analysis, "is_red"), None);
+    assert_eq!(value(&analysis, "red_is_red"), value(&analysis, "true"));
+    assert_eq!(value(&analysis, "green_is_red"), value(&analysis, "false"));
+    assert_eq!(value(&analysis, "bool_is_red"), value(&analysis, "false"));
+}
+
+#[test]
+fn asserts_checking_the_same_types_are_merged() {
+    let program = "
+        Contents IS ANY Type
+        Box IS NEW_TYPE(contents IS ANY Contents)
+        first IS Box(True).new(true)
+        ";
+    let once = analyze(program);
+    let twice = analyze(&format!("{}second IS Box(True).new(true)", program));
+    let (once, twice) = (env3(&once).stats(), env3(&twice).stats());
+    assert!(twice.asserts > once.asserts);
+    assert!(twice.merged_asserts > once.merged_asserts);
+    assert_eq!(
+        twice.asserts - twice.merged_asserts,
+        once.asserts - once.merged_asserts
+    );
+
+    let analysis = analyze(&format!(
+        "{}second IS Box(True).new(false)
+        third IS Box(True).new(false)",
+        program
+    ));
+    // Both wrong arguments are reported by one error.
+    assert_eq!(diagnostic_codes(&analysis).len(), 1);
+    let Err(errors) = &analysis.env3 else { panic!("The program compiled.") };
+    let error = errors[0].format_plain(&analysis.sources);
+    assert_eq!(
+        error.matches("this has type `False`").count(),
+        2,
+        "{}",
+        error
+    );
+}
@@ -1,4 +1,8 @@
-use std::{fs::FileType, path::Path};
+use std::{
+    fs::FileType,
+    io::{self, ErrorKind},
+    path::Path,
+};
 
 #[derive(Debug, Clone)]
 pub struct FileNode {
@@ -7,33 +11,46 @@ pub struct FileNode {
 }
 
 impl FileNode {
-    fn get_file_impl(&self, index: &mut usize) -> Option<(String, &str)> {
-        if *index == 0 {
-            Some(("".to_owned(), &self.self_content))
-        } else {
-            *index -= 1;
-            for child in &self.children {
-                if let Some((path, content)) = child.1.get_file_impl(index) {
-                    return Some((format!("/{}{}", child.0, path), content));
-                }
-            }
-            None
+    fn collect_files<'a>(&'a self, prefix: String, into: &mut Vec<(String, &'a str)>) {
+        into.push((prefix.clone(), &self.self_content));
+        for (name, child) in &self.children {
+            child.collect_files(format!("{}/{}", prefix, name), into);
         }
     }
 
-    pub fn get_file(&self, index: usize) -> (String, &str) {
-        if index == 0 {
-            (format!("PLACEHOLDER"), "")
-        } else {
-            self.get_file_impl(&mut (index - 1)).unwrap()
+    /// Lists the path and content of every file in the tree. The file at
+    /// index `n` in the returned list is the file which positions refer to
+    /// with a file index of `n + 1`.
+    pub fn files(&self) -> Vec<(String, &str)> {
+        let mut files = Vec::new();
+        self.collect_files(String::new(), &mut files);
+        files
+    }
+
+    fn collect_files_mut<'a>(
+        &'a mut self,
+        prefix: String,
+        into: &mut Vec<(String, &'a mut String)>,
+    ) {
+        into.push((prefix.clone(), &mut self.self_content));
+        for (name, child) in &mut self.children {
+            child.collect_files_mut(format!("{}/{}", prefix, name), into);
         }
     }
+
+    /// Like [`FileNode::files`], but allows modifying the content of each
+    /// file.
+    pub fn files_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut files = Vec::new();
+        self.collect_files_mut(String::new(), &mut files);
+        files
+    }
 }
 
-fn read_folder_contents(at: &Path) -> Vec<(String, FileNode)> {
+fn read_folder_contents(at: &Path) -> io::Result<Vec<(String, FileNode)>> {
     let mut results = Vec::new();
-    for entry in std::fs::read_dir(at).unwrap() {
-        let entry = entry.unwrap();
+    for entry in std::fs::read_dir(at)? {
+        let entry = entry?;
         let mut name = entry.file_name().to_string_lossy().to_string();
         let is_dir = entry
             .file_type()
@@ -52,38 +69,42 @@ fn read_folder_contents(at: &Path) -> Vec<(String, FileNode)> {
         {
             continue;
         }
-        if let Some(item) = read_path(&entry.path().with_extension("")) {
+        if let Some(item) = read_path(&entry.path().with_extension(""))? {
             results.push((name, item))
         }
     }
-    results
+    Ok(results)
 }
 
-fn read_path(at: &Path) -> Option<FileNode> {
+fn read_path(at: &Path) -> io::Result<Option<FileNode>> {
     let folder_path = at;
     let mut children = Vec::new();
     if folder_path.exists() && folder_path.is_dir() {
-        children = read_folder_contents(folder_path);
+        children = read_folder_contents(folder_path)?;
     }
     children.sort_by(|a, b| a.0.cmp(&b.0));
     let file_path = at.with_extension("sr");
     if file_path.exists() && file_path.is_file() {
-        let content = std::fs::read_to_string(file_path).unwrap();
-        Some(FileNode {
+        let content = std::fs::read_to_string(file_path)?;
+        Ok(Some(FileNode {
             self_content: content,
             children,
-        })
+        }))
     } else if children.len() > 0 {
-        Some(FileNode {
+        Ok(Some(FileNode {
             self_content: String::new(),
             children,
-        })
+        }))
     } else {
-        None
+        Ok(None)
     }
 }
 
-pub fn read_root<'a>(at: impl AsRef<Path>) -> Option<FileNode> {
-    let root_path = at.as_ref();
-    read_path(&root_path)
+/// Reads the program at the given path, written without the `.sr` extension.
+/// The path can name a file, a folder of files, or both.
+pub fn read_root(at: impl AsRef<Path>) -> io::Result<FileNode> {
+    read_path(at.as_ref())?.ok_or_else(|| {
+        let message = "there is no .sr file or folder of .sr files there";
+        io::Error::new(ErrorKind::NotFound, message)
+    })
 }
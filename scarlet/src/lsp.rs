@@ -0,0 +1,14 @@
+//! A language server which speaks the Language Server Protocol over stdin and
+//! stdout, for use by editors.
+
+mod completion;
+mod hover;
+mod inlay_hints;
+mod protocol;
+mod rename;
+mod semantic_tokens;
+mod server;
+mod signature_help;
+mod symbols;
+
+pub use server::serve;
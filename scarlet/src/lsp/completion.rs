@@ -0,0 +1,38 @@
+use serde_json::{json, Value};
+
+use super::{protocol::position_to_offset, server::Server};
+use crate::analysis::{completions, CompletionKind};
+
+fn lsp_kind(kind: CompletionKind) -> u64 {
+    match kind {
+        CompletionKind::Module => 9,
+        CompletionKind::Type => 7,
+        CompletionKind::Parameter => 25,
+        CompletionKind::Value => 21,
+        CompletionKind::Constructor => 4,
+        CompletionKind::NamedArgument => 5,
+    }
+}
+
+/// Suggests identifiers, members and named arguments which could be written
+/// at the cursor.
+pub fn handle(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let offset = position_to_offset(&text, &params["position"]);
+    let Some(analysis) = server.last_analysis() else { return Value::Null };
+    let items = completions(analysis, file_index, &text[..offset])
+        .into_iter()
+        .enumerate()
+        .map(|(rank, completion)| {
+            json!({
+                "label": completion.label,
+                "kind": lsp_kind(completion.kind),
+                "detail": completion.r#type,
+                "insertText": completion.insert_text,
+                "sortText": format!("{:04}", rank),
+            })
+        })
+        .collect();
+    Value::Array(items)
+}
@@ -0,0 +1,29 @@
+use serde_json::{json, Value};
+
+use super::{
+    protocol::{offset_to_position, position_to_offset},
+    server::Server,
+};
+use crate::analysis::{item_markdown, reference_at};
+
+/// Describes the item whose name is under the cursor, along with its
+/// documentation.
+pub fn handle(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let offset = position_to_offset(&text, &params["position"]);
+    let Some(analysis) = server.analysis() else { return Value::Null };
+    let reference = reference_at(analysis, file_index, offset);
+    let Some(reference) = reference else { return Value::Null };
+    let range = reference.position.range();
+    json!({
+        "contents": {
+            "kind": "markdown",
+            "value": item_markdown(analysis, &reference.name, reference.target),
+        },
+        "range": {
+            "start": offset_to_position(&text, range.start),
+            "end": offset_to_position(&text, range.end),
+        },
+    })
+}
@@ -0,0 +1,31 @@
+use serde_json::{json, Value};
+
+use super::{
+    protocol::{offset_to_position, position_to_offset},
+    server::Server,
+};
+use crate::analysis::argument_hints;
+
+/// Shows the name of the parameter each positional argument is assigned to.
+pub fn handle(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let start = position_to_offset(&text, &params["range"]["start"]);
+    let end = position_to_offset(&text, &params["range"]["end"]);
+    let Some(analysis) = server.analysis() else { return Value::Null };
+    let mut hints = Vec::new();
+    for hint in argument_hints(analysis) {
+        let offset = hint.position.range().start;
+        if hint.position.file_index() != file_index || offset < start || offset > end {
+            continue;
+        }
+        let Some(name) = hint.parameter_name else { continue };
+        hints.push(json!({
+            "position": offset_to_position(&text, offset),
+            "label": format!("{} IS", name),
+            "kind": 2,
+            "paddingRight": true,
+        }));
+    }
+    Value::Array(hints)
+}
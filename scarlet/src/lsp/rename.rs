@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+
+use super::{
+    protocol::{offset_to_position, path_to_uri, position_to_offset},
+    server::Server,
+};
+use crate::analysis::{reference_at, rename};
+
+const REQUEST_FAILED: i64 = -32803;
+
+/// Renames the item whose name is at the cursor, along with every reference
+/// to it.
+pub fn handle(server: &mut Server, params: &Value) -> Result<Value, (i64, String)> {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let new_name = params["newName"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Ok(Value::Null) };
+    let offset = position_to_offset(&text, &params["position"]);
+    let Some(analysis) = server.analysis() else {
+        let message = "Syntax errors must be fixed before renaming anything.";
+        return Err((REQUEST_FAILED, message.to_owned()));
+    };
+    let Some(reference) = reference_at(analysis, file_index, offset) else {
+        let message = "There is nothing to rename here.";
+        return Err((REQUEST_FAILED, message.to_owned()));
+    };
+    let rename = rename(analysis, reference.target, new_name)
+        .map_err(|err| (REQUEST_FAILED, err.format_plain(&analysis.sources)))?;
+
+    let mut file_edits = Vec::new();
+    for (index, (_, content)) in analysis.file_tree.files().into_iter().enumerate() {
+        let edits = rename
+            .edits
+            .iter()
+            .filter(|edit| edit.position.file_index() == index + 1)
+            .map(|edit| {
+                let range = edit.position.range();
+                json!({
+                    "range": {
+                        "start": offset_to_position(content, range.start),
+                        "end": offset_to_position(content, range.end),
+                    },
+                    "newText": edit.new_text,
+                })
+            })
+            .collect::<Vec<_>>();
+        if !edits.is_empty() {
+            file_edits.push((index + 1, edits));
+        }
+    }
+
+    let mut document_changes = Vec::new();
+    for (file_index, edits) in file_edits {
+        let Some(path) = server.file_path(file_index) else { continue };
+        document_changes.push(json!({
+            "textDocument": { "uri": path_to_uri(&path), "version": null },
+            "edits": edits,
+        }));
+    }
+    for file_rename in rename.file_renames {
+        let Some(from) = server.absolute_path(&file_rename.from) else { continue };
+        let Some(to) = server.absolute_path(&file_rename.to) else { continue };
+        // Files in a folder belong to the file next to it, so both have to be
+        // renamed.
+        for (from, to) in [
+            (from.clone(), to.clone()),
+            (from.with_extension(""), to.with_extension("")),
+        ] {
+            if from.exists() {
+                document_changes.push(json!({
+                    "kind": "rename",
+                    "oldUri": path_to_uri(&from),
+                    "newUri": path_to_uri(&to),
+                }));
+            }
+        }
+    }
+    Ok(json!({ "documentChanges": document_changes }))
+}
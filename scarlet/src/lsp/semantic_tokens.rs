@@ -0,0 +1,70 @@
+use serde_json::{json, Value};
+
+use super::{protocol::offset_to_position, server::Server};
+use crate::analysis::{semantic_tokens, TokenKind};
+
+const MODIFIER_DECLARATION: u64 = 1 << 0;
+const MODIFIER_DEFAULT_LIBRARY: u64 = 1 << 1;
+
+fn lsp_type(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Type => "type",
+        TokenKind::Parameter => "parameter",
+        TokenKind::Constructor => "function",
+        TokenKind::Module => "namespace",
+        TokenKind::Builtin => "macro",
+        TokenKind::Value => "variable",
+    }
+}
+
+/// Describes the token types and modifiers the server will use.
+pub fn legend() -> Value {
+    let token_types: Vec<_> = TokenKind::ALL.iter().map(|&kind| lsp_type(kind)).collect();
+    json!({
+        "tokenTypes": token_types,
+        "tokenModifiers": ["declaration", "defaultLibrary"],
+    })
+}
+
+/// Classifies every name in a file by what it refers to.
+pub fn handle(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let Some(analysis) = server.analysis() else { return Value::Null };
+    // Each token is encoded as five numbers, with its position relative to
+    // the token before it.
+    let mut data = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for token in semantic_tokens(analysis) {
+        if token.position.file_index() != file_index {
+            continue;
+        }
+        let range = token.position.range();
+        let start = offset_to_position(&text, range.start);
+        let end = offset_to_position(&text, range.end);
+        let line = start["line"].as_u64().unwrap_or(0);
+        let character = start["character"].as_u64().unwrap_or(0);
+        let length = end["character"].as_u64().unwrap_or(0) - character;
+        if line != previous_line {
+            previous_start = 0;
+        }
+        let kind = TokenKind::ALL.iter().position(|&k| k == token.kind).unwrap();
+        let mut modifiers = 0;
+        if token.declaration {
+            modifiers |= MODIFIER_DECLARATION;
+        }
+        if token.language_item {
+            modifiers |= MODIFIER_DEFAULT_LIBRARY;
+        }
+        data.extend([
+            line - previous_line,
+            character - previous_start,
+            length,
+            kind as u64,
+            modifiers,
+        ]);
+        previous_line = line;
+        previous_start = character;
+    }
+    json!({ "data": data })
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufReader},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use super::{
+    completion, hover, inlay_hints,
+    protocol::{error_response, read_message, response, uri_to_path, write_message},
+    rename, semantic_tokens, signature_help, symbols,
+};
+use crate::{analysis::Analysis, file_tree, source_map::SourceMap};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+
+pub struct Server {
+    root: Option<PathBuf>,
+    /// The latest content of every file the editor has open, which takes
+    /// precedence over what is on disk.
+    open_documents: HashMap<PathBuf, String>,
+    /// The most recent analysis which succeeded.
+    analysis: Option<Analysis>,
+    /// Whether `analysis` reflects the latest content of every file.
+    analysis_is_current: bool,
+    /// Whether anything changed since the program was last analyzed.
+    needs_analysis: bool,
+}
+
+impl Server {
+    fn new(root: Option<PathBuf>) -> Self {
+        Self {
+            root,
+            open_documents: HashMap::new(),
+            analysis: None,
+            analysis_is_current: false,
+            needs_analysis: true,
+        }
+    }
+
+    pub fn absolute_path(&self, relative_path: &str) -> Option<PathBuf> {
+        let root = self.root.as_ref()?.to_string_lossy();
+        Some(PathBuf::from(format!("{}{}.sr", root, relative_path)))
+    }
+
+    /// Returns the path on disk of the file with the given index.
+    pub fn file_path(&self, file_index: usize) -> Option<PathBuf> {
+        let analysis = self.analysis.as_ref()?;
+        Some(PathBuf::from(&analysis.sources.file(file_index)?.path))
+    }
+
+    /// Returns the index and latest content of the file a document URI
+    /// refers to.
+    pub fn file(&mut self, uri: &str) -> Option<(usize, String)> {
+        let path = uri_to_path(uri)?;
+        let file_count = self.last_analysis()?.sources.files().len();
+        let index =
+            (1..=file_count).find(|&index| self.file_path(index).as_ref() == Some(&path))?;
+        let content = match self.open_documents.get(&path) {
+            Some(content) => content.clone(),
+            None => fs::read_to_string(&path).ok()?,
+        };
+        Some((index, content))
+    }
+
+    fn update_analysis(&mut self) -> Option<()> {
+        if !self.needs_analysis {
+            return Some(());
+        }
+        self.needs_analysis = false;
+        self.analysis_is_current = false;
+        let mut tree = file_tree::read_root(self.root.as_ref()?).ok()?;
+        for (relative_path, content) in tree.files_mut() {
+            let path = self.absolute_path(&relative_path)?;
+            if let Some(open) = self.open_documents.get(&path) {
+                *content = open.clone();
+            }
+        }
+        // The compiler still panics on some malformed programs, which should
+        // not take the whole server down with it.
+        let root = self.root.as_ref()?.to_string_lossy();
+        let sources = SourceMap::new(&root, &tree);
+        let analysis = panic::catch_unwind(AssertUnwindSafe(|| Analysis::new(tree, sources)));
+        self.analysis = Some(analysis.ok()?.ok()?);
+        self.analysis_is_current = true;
+        Some(())
+    }
+
+    /// Returns the analysis of the current state of the program, recomputing
+    /// it if anything changed since it was last requested. Returns `None` if
+    /// the program cannot be parsed.
+    pub fn analysis(&mut self) -> Option<&Analysis> {
+        self.update_analysis();
+        if self.analysis_is_current {
+            self.analysis.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Server::analysis`], but falls back to the most recent analysis
+    /// which succeeded. This is useful while code is being typed, when it
+    /// usually cannot be parsed.
+    pub fn last_analysis(&mut self) -> Option<&Analysis> {
+        self.update_analysis();
+        self.analysis.as_ref()
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => {
+                if self.root.is_none() {
+                    self.root = params["rootUri"]
+                        .as_str()
+                        .and_then(uri_to_path)
+                        .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+                }
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "inlayHintProvider": true,
+                        "renameProvider": true,
+                        "documentSymbolProvider": true,
+                        "workspaceSymbolProvider": true,
+                        "semanticTokensProvider": {
+                            "legend": semantic_tokens::legend(),
+                            "full": true,
+                        },
+                        "signatureHelpProvider": {
+                            "triggerCharacters": ["(", " ", ","],
+                        },
+                        "completionProvider": {
+                            "triggerCharacters": ["."],
+                        },
+                    },
+                    "serverInfo": { "name": "scarlet" },
+                }))
+            }
+            "shutdown" => Ok(Value::Null),
+            "textDocument/completion" => Ok(completion::handle(self, params)),
+            "textDocument/documentSymbol" => Ok(symbols::handle_document(self, params)),
+            "textDocument/hover" => Ok(hover::handle(self, params)),
+            "textDocument/inlayHint" => Ok(inlay_hints::handle(self, params)),
+            "textDocument/rename" => rename::handle(self, params),
+            "textDocument/semanticTokens/full" => Ok(semantic_tokens::handle(self, params)),
+            "textDocument/signatureHelp" => Ok(signature_help::handle(self, params)),
+            "workspace/symbol" => Ok(symbols::handle_workspace(self, params)),
+            _ => Err((METHOD_NOT_FOUND, format!("{} is not supported.", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) {
+        let document = &params["textDocument"];
+        let path = document["uri"].as_str().and_then(uri_to_path);
+        match (method, path) {
+            ("textDocument/didOpen", Some(path)) => {
+                let text = document["text"].as_str().unwrap_or_default();
+                self.open_documents.insert(path, text.to_owned());
+                self.needs_analysis = true;
+                // Files are usually opened in a state that can be analyzed,
+                // which gives features that work on incomplete code
+                // something to fall back on once editing starts.
+                self.update_analysis();
+            }
+            ("textDocument/didChange", Some(path)) => {
+                // We only ask for full document synchronization, so the last
+                // change contains the entire document.
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()).map(|c| &c["text"]) {
+                    let text = text.as_str().unwrap_or_default();
+                    self.open_documents.insert(path, text.to_owned());
+                    self.needs_analysis = true;
+                }
+            }
+            ("textDocument/didClose", Some(path)) => {
+                self.open_documents.remove(&path);
+                self.needs_analysis = true;
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn serve(root: Option<&Path>) {
+    let mut input = BufReader::new(io::stdin());
+    let mut output = io::stdout();
+    let root = root.map(|root| root.canonicalize().unwrap_or_else(|_| root.to_path_buf()));
+    let mut server = Server::new(root);
+    while let Some(message) = read_message(&mut input) {
+        let Some(method) = message["method"].as_str() else { continue };
+        if method == "exit" {
+            break;
+        }
+        let params = &message["params"];
+        if let Some(id) = message.get("id").cloned() {
+            // Features look at the analysis with the same code which panics on
+            // some malformed programs, so a request which panics is answered
+            // with an error instead of taking the server down.
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| server.handle_request(method, params)));
+            let reply = match result {
+                Ok(Ok(result)) => response(id, result),
+                Ok(Err((code, message))) => error_response(id, code, message),
+                Err(_) => {
+                    let message = format!("The server crashed while handling {}.", method);
+                    error_response(id, INTERNAL_ERROR, message)
+                }
+            };
+            write_message(&mut output, &reply);
+        } else {
+            server.handle_notification(method, params);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use serde_json::{json, Value};
+
+use super::{protocol::position_to_offset, server::Server};
+use crate::analysis::call_at;
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Shows the parameters of the substitution being written at the cursor.
+pub fn handle(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let offset = position_to_offset(&text, &params["position"]);
+    let Some(analysis) = server.last_analysis() else { return Value::Null };
+    let Some(call) = call_at(analysis, file_index, &text[..offset]) else { return Value::Null };
+    let (label, ranges) = call.signature.label(&call.callee);
+    let parameters = call
+        .signature
+        .parameters
+        .iter()
+        .zip(ranges)
+        .map(|(parameter, range)| {
+            let start = utf16_len(&label[..range.start]);
+            let end = utf16_len(&label[..range.end]);
+            json!({
+                "label": [start, end],
+                "documentation": format!("Type: {}", parameter.r#type),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "signatures": [{
+            "label": label,
+            "parameters": parameters,
+        }],
+        "activeSignature": 0,
+        "activeParameter": call.active_parameter,
+    })
+}
@@ -0,0 +1,108 @@
+use serde_json::{json, Value};
+
+use super::{
+    protocol::{offset_to_position, path_to_uri},
+    server::Server,
+};
+use crate::{
+    analysis::{file_outline, outline, Symbol, TokenKind},
+    diagnostic::Position,
+};
+
+fn lsp_kind(kind: TokenKind) -> u64 {
+    match kind {
+        TokenKind::Module => 2,
+        TokenKind::Type => 5,
+        TokenKind::Constructor => 9,
+        TokenKind::Builtin => 12,
+        TokenKind::Value => 13,
+        TokenKind::Parameter => 26,
+    }
+}
+
+fn range(text: &str, position: Position) -> Value {
+    let range = position.range();
+    json!({
+        "start": offset_to_position(text, range.start),
+        "end": offset_to_position(text, range.end),
+    })
+}
+
+fn document_symbol(text: &str, symbol: &Symbol) -> Option<Value> {
+    let position = symbol.position?;
+    let label_position = symbol.label_position.unwrap_or(position);
+    let children: Vec<_> = symbol
+        .children
+        .iter()
+        .filter_map(|child| document_symbol(text, child))
+        .collect();
+    Some(json!({
+        "name": symbol.name,
+        "detail": symbol.kind.name(),
+        "kind": lsp_kind(symbol.kind),
+        "range": range(text, position),
+        "selectionRange": range(text, label_position),
+        "children": children,
+    }))
+}
+
+/// Lists the labeled items in a file, nested inside the structures they are
+/// defined in.
+pub fn handle_document(server: &mut Server, params: &Value) -> Value {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+    let Some((file_index, text)) = server.file(uri) else { return Value::Null };
+    let Some(analysis) = server.analysis() else { return Value::Null };
+    let symbols: Vec<_> = file_outline(analysis, file_index)
+        .iter()
+        .filter_map(|symbol| document_symbol(&text, symbol))
+        .collect();
+    Value::Array(symbols)
+}
+
+fn collect_matches<'a>(
+    symbols: &'a [Symbol],
+    container: &str,
+    query: &str,
+    into: &mut Vec<(&'a Symbol, String)>,
+) {
+    for symbol in symbols {
+        if symbol.name.to_lowercase().contains(query) {
+            into.push((symbol, container.to_owned()));
+        }
+        let container = if container.is_empty() {
+            symbol.name.clone()
+        } else {
+            format!("{}.{}", container, symbol.name)
+        };
+        collect_matches(&symbol.children, &container, query, into);
+    }
+}
+
+/// Finds labeled items anywhere in the program whose names contain the
+/// query.
+pub fn handle_workspace(server: &mut Server, params: &Value) -> Value {
+    let query = params["query"].as_str().unwrap_or_default().to_lowercase();
+    let Some(analysis) = server.last_analysis() else { return Value::Null };
+    let symbols = outline(analysis);
+    let mut matches = Vec::new();
+    collect_matches(&symbols, "", &query, &mut matches);
+    let matches: Vec<_> = matches
+        .into_iter()
+        .filter_map(|(symbol, container)| {
+            let position = symbol.position?;
+            let text = &analysis.sources.file(position.file_index())?.content;
+            Some((symbol, container, position, range(text, position)))
+        })
+        .collect();
+    let mut result = Vec::new();
+    for (symbol, container, position, range) in matches {
+        let Some(path) = server.file_path(position.file_index()) else { continue };
+        result.push(json!({
+            "name": symbol.name,
+            "kind": lsp_kind(symbol.kind),
+            "location": { "uri": path_to_uri(&path), "range": range },
+            "containerName": container,
+        }));
+    }
+    Value::Array(result)
+}
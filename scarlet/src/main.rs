@@ -11,15 +11,18 @@
 #![feature(fmt_internals)]
 #![feature(type_name_of_val)]
 
+pub mod analysis;
 pub mod definitions;
 pub mod diagnostic;
 mod entry;
 pub mod environment;
 mod file_tree;
 pub mod item;
+mod lsp;
 pub mod parser;
 pub mod scope;
 mod shared;
+mod source_map;
 mod util;
 
 fn main() {
@@ -5,10 +5,17 @@ mod parse;
 mod phrase;
 mod scarlet_phrases;
 mod stack;
+mod textmate;
+mod tree_sitter;
 mod util;
 
 pub use node::{Node, NodeChild};
-pub use parse::{parse_tree, ParseContext};
+pub use parse::{
+    open_labels, open_member_access, open_substitution, parse_incomplete, parse_tree,
+    OpenArgument, OpenSubstitution, ParseContext, LINE_COMMENT,
+};
+pub use textmate::textmate_grammar;
+pub use tree_sitter::{tree_sitter_corpus, tree_sitter_grammar};
 
 use self::phrase::CreateContext;
 use crate::{
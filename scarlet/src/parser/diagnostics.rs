@@ -1,8 +1,9 @@
 use super::node::Node;
-use crate::diagnostic::{Diagnostic, Position};
+use crate::diagnostic::{Diagnostic, ErrorCode, Position};
 
 pub fn incomplete_phrase_error(node: &Node) -> Diagnostic {
     Diagnostic::new()
+        .with_code(ErrorCode::IncompletePhrase)
         .with_text_error(format!(
             "This looks like a \"{}\" phrase, but it is incomplete:",
             node.phrase
@@ -12,6 +13,7 @@ pub fn incomplete_phrase_error(node: &Node) -> Diagnostic {
 
 pub fn unrecognized_input(position: Position) -> Diagnostic {
     Diagnostic::new()
+        .with_code(ErrorCode::UnrecognizedInput)
         .with_text_error("Not sure what to do with the following input:".to_owned())
         .with_source_code_block_error(position)
 }
@@ -2,7 +2,7 @@ use std::fmt::{self, Debug, Formatter};
 
 use super::phrase::{CreateContext, CreateResult, PhraseTable};
 use crate::{
-    diagnostic::{Diagnostic, Position},
+    diagnostic::{Diagnostic, ErrorCode, Position},
     shared::indented,
 };
 
@@ -54,6 +54,8 @@ pub struct Node<'x> {
     pub phrase: &'static str,
     pub children: Vec<NodeChild<'x>>,
     pub position: Position,
+    /// The text of the `##` comments written directly before the node.
+    pub doc: Option<String>,
 }
 
 impl<'x> Debug for Node<'x> {
@@ -95,6 +97,7 @@ impl<'x> Node<'x> {
             Ok(self.children[0].as_text())
         } else {
             Err(Diagnostic::new()
+                .with_code(ErrorCode::ExpectedIdentifier)
                 .with_text_error(format!(
                     "Expected an identifier, got a {} phrase instead:",
                     self.phrase
@@ -103,6 +106,16 @@ impl<'x> Node<'x> {
         }
     }
 
+    /// Returns the documentation written before the phrase, which is stored
+    /// on the first node in it.
+    pub fn doc(&self) -> Option<&str> {
+        match (&self.doc, self.children.first()) {
+            (Some(doc), _) => Some(doc),
+            (None, Some(NodeChild::Node(first))) => first.doc(),
+            _ => None,
+        }
+    }
+
     pub fn as_item(&self, ctx: &mut CreateContext) -> CreateResult {
         let item = ctx
             .pc
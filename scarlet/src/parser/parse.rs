@@ -9,11 +9,20 @@ use super::{
     util,
 };
 use crate::{
-    diagnostic::{Diagnostic, Position},
+    diagnostic::{Diagnostic, ErrorCode, Position},
     file_tree::FileNode,
     parser::{diagnostics::unrecognized_input, matchh, scarlet_phrases, stack::Stack},
 };
 
+/// Starts a comment which lasts until the end of the line.
+pub const LINE_COMMENT: &str = "#";
+/// Starts a comment which documents the item defined after it.
+pub const DOC_COMMENT: &str = "##";
+/// Starts a comment which lasts until a matching [`BLOCK_COMMENT_END`]. Block
+/// comments can be nested.
+pub const BLOCK_COMMENT_START: &str = "#=";
+pub const BLOCK_COMMENT_END: &str = "=#";
+
 pub struct ParseContext {
     pub(crate) phrases_sorted_by_priority: PhraseTable,
 }
@@ -38,6 +47,7 @@ fn push_match<'a>(
     matchh: MatchSuccess<'a>,
     to: &mut Stack<'a>,
     position: Position,
+    doc: Option<String>,
 ) -> Result<(), Diagnostic> {
     let mut append = Vec::new();
     if matchh.phrase == "identifier"
@@ -46,6 +56,7 @@ fn push_match<'a>(
             .contains(|x: char| x.is_lowercase() || x.is_dec_digit())
     {
         return Err(Diagnostic::new()
+            .with_code(ErrorCode::UnrecognizedKeyword)
             .with_text_error(format!("Unrecognized keyword"))
             .with_source_code_block_error(position));
     }
@@ -78,7 +89,11 @@ fn push_match<'a>(
                 text: ",",
                 continuation_of: None,
             };
-            push_match(pt, matchh, to, Default::default())?;
+            // Items written next to each other are separated by an invisible
+            // comma at the start of the second item.
+            let start = position.range().start;
+            let comma_position = Position::new(position.file_index(), start..start);
+            push_match(pt, matchh, to, comma_position, None)?;
         }
     }
     append.push(NodeChild::Text(matchh.text));
@@ -97,17 +112,21 @@ fn push_match<'a>(
             phrase: matchh.phrase,
             children: append,
             position,
+            doc,
         });
     }
     Ok(())
 }
 
-fn parse<'a>(
+/// Parses as much of the input as possible, returning the phrases which are
+/// still being parsed once the input runs out.
+fn parse_to_stack<'a>(
     input: &'a str,
     ctx: &'a ParseContext,
     file_index: usize,
-) -> Result<Option<Node<'a>>, Diagnostic> {
-    let r_whitespace = Regex::new(r"[ \r\n\t]+|#[^\n]*").unwrap();
+) -> Result<Stack<'a>, Diagnostic> {
+    let line_comment = regex::escape(LINE_COMMENT);
+    let r_whitespace = Regex::new(&format!(r"[ \r\n\t]+|{}[^\n]*", line_comment)).unwrap();
 
     let ParseContext {
         phrases_sorted_by_priority: phrases,
@@ -118,16 +137,17 @@ fn parse<'a>(
 
     let mut input_position = 0;
     let mut comment_depth = 0;
+    // Lines of `##` comments which have not been attached to a phrase yet.
+    let mut doc_lines: Vec<&str> = Vec::new();
     while input_position < input.len() {
         let match_against = &input[input_position..];
-        if match_against.len() < 2 {
-        } else if &match_against[0..2] == "#=" {
+        if match_against.starts_with(BLOCK_COMMENT_START) {
             comment_depth += 1;
-            input_position += 2;
+            input_position += BLOCK_COMMENT_START.len();
             continue;
-        } else if &match_against[0..2] == "=#" {
+        } else if match_against.starts_with(BLOCK_COMMENT_END) {
             comment_depth -= 1;
-            input_position += 2;
+            input_position += BLOCK_COMMENT_END.len();
             continue;
         } else if comment_depth > 0 {
             input_position += 1;
@@ -141,9 +161,20 @@ fn parse<'a>(
                 file_index as usize,
                 start_char..start_char + matchh.text.len(),
             );
-            push_match(phrases, matchh, &mut stack, file_position)?;
+            let doc = if !doc_lines.is_empty() {
+                Some(doc_lines.join("\n"))
+            } else {
+                None
+            };
+            doc_lines.clear();
+            push_match(phrases, matchh, &mut stack, file_position, doc)?;
         } else if let Some(matchh) = matchh::anchored_find(&r_whitespace, match_against) {
             input_position += matchh.len();
+            if let Some(line) = matchh.strip_prefix(DOC_COMMENT) {
+                doc_lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end());
+            } else if matchh.starts_with(LINE_COMMENT) {
+                doc_lines.clear();
+            }
         } else {
             let start_char = input.len() - match_against.len();
             let file_position = Position::new(file_index as usize, start_char..start_char + 1);
@@ -151,6 +182,146 @@ fn parse<'a>(
         }
     }
 
+    Ok(stack)
+}
+
+/// Parses code which is expected to end partway through a phrase, such as
+/// the code before the cursor in an editor. Returns the phrases which have
+/// not been completed, from outermost to innermost.
+pub fn parse_incomplete<'a>(
+    input: &'a str,
+    ctx: &'a ParseContext,
+    file_index: usize,
+) -> Result<Vec<Node<'a>>, Diagnostic> {
+    Ok(parse_to_stack(input, ctx, file_index)?.0)
+}
+
+/// An argument of a substitution which has not been closed yet.
+#[derive(Clone, Debug)]
+pub enum OpenArgument {
+    Positional,
+    /// An argument written as `name IS value`, where the value may not have
+    /// been written yet.
+    Named(String),
+}
+
+/// A substitution which has been opened but not closed, such as the one
+/// being written at the cursor in an editor.
+#[derive(Clone, Debug)]
+pub struct OpenSubstitution<'a> {
+    pub base: Node<'a>,
+    pub arguments: Vec<OpenArgument>,
+    /// Whether the last argument forms a complete phrase, as opposed to
+    /// being something like `name IS` with nothing after it.
+    pub last_argument_complete: bool,
+}
+
+/// Finds the innermost substitution which is still open at the end of the
+/// given code, along with the arguments it has been given so far.
+pub fn open_substitution<'a>(
+    input: &'a str,
+    ctx: &'a ParseContext,
+    file_index: usize,
+) -> Option<OpenSubstitution<'a>> {
+    let pt = &ctx.phrases_sorted_by_priority;
+    let mut nodes = parse_incomplete(input, ctx, file_index).ok()?;
+    let index = nodes
+        .iter()
+        .rposition(|node| node.phrase == "substitution" && node.children.len() == 2)?;
+    let mut stack = Stack(nodes.split_off(index));
+    stack.collapse_to_precedence(pt, 255).ok()?;
+    let mut arguments = Vec::new();
+    for node in &stack.0[1..] {
+        let list = if node.is_complete(pt) {
+            NodeChild::Node(node.clone())
+        } else if node.phrase == "multiple items" || node.phrase == "is" {
+            node.children[0].clone()
+        } else {
+            continue;
+        };
+        for argument in util::collect_comma_list(&list) {
+            arguments.push(OpenArgument::Positional);
+            if let Some(Ok((name, _))) = argument.as_is() {
+                *arguments.last_mut().unwrap() = OpenArgument::Named(name.to_owned());
+            }
+        }
+        if node.phrase == "is" && !node.is_complete(pt) {
+            let name = node.children[0].as_ident().ok()?;
+            *arguments.last_mut().unwrap() = OpenArgument::Named(name.to_owned());
+        }
+    }
+    let last_argument_complete = stack.0.last().unwrap().is_complete(pt);
+    let base = stack.0.swap_remove(0).children.swap_remove(0);
+    let NodeChild::Node(base) = base else { return None };
+    Some(OpenSubstitution {
+        base,
+        arguments,
+        last_argument_complete,
+    })
+}
+
+/// If the given code ends with a member access which is waiting for the name
+/// of its member, as in `base.`, returns the base of the member access.
+pub fn open_member_access<'a>(
+    input: &'a str,
+    ctx: &'a ParseContext,
+    file_index: usize,
+) -> Option<Node<'a>> {
+    let mut nodes = parse_incomplete(input, ctx, file_index).ok()?;
+    let last = nodes.pop()?;
+    if last.phrase == "member access" && last.children.len() == 2 {
+        let NodeChild::Node(base) = last.children.into_iter().next()? else { return None };
+        Some(base)
+    } else {
+        None
+    }
+}
+
+fn collect_labels<'a>(node: &Node<'a>, into: &mut Vec<&'a str>) {
+    if node.phrase == "is" && node.children.len() == 3 {
+        if let NodeChild::Node(label) = &node.children[0] {
+            if let Ok(label) = label.as_ident() {
+                into.push(label);
+            }
+        }
+    } else if node.phrase == "multiple items" {
+        for child in &node.children {
+            if let NodeChild::Node(child) = child {
+                collect_labels(child, into);
+            }
+        }
+    }
+}
+
+/// Returns the labels of the fields which have been written so far in the
+/// structures that are still open at the end of the given code, skipping any
+/// labels given to arguments of open substitutions. Labels from the innermost
+/// structure come first.
+pub fn open_labels<'a>(input: &'a str, ctx: &'a ParseContext, file_index: usize) -> Vec<&'a str> {
+    let Ok(nodes) = parse_incomplete(input, ctx, file_index) else { return vec![] };
+    let mut structures = vec![Vec::new()];
+    let mut in_structure = true;
+    for node in &nodes {
+        match node.phrase {
+            "structure" if node.children.len() < 3 => {
+                structures.push(Vec::new());
+                in_structure = true;
+            }
+            "substitution" | "new type" if node.children.len() < 4 => in_structure = false,
+            _ if in_structure => collect_labels(node, structures.last_mut().unwrap()),
+            _ => (),
+        }
+    }
+    structures.into_iter().rev().flatten().collect()
+}
+
+pub(super) fn parse<'a>(
+    input: &'a str,
+    ctx: &'a ParseContext,
+    file_index: usize,
+) -> Result<Option<Node<'a>>, Diagnostic> {
+    let mut stack = parse_to_stack(input, ctx, file_index)?;
+    let phrases = &ctx.phrases_sorted_by_priority;
     while stack.0.len() > 1 {
         stack.collapse(phrases)?;
     }
@@ -1,13 +1,20 @@
 mod any;
+mod anything;
 mod as_language_item;
 mod builtin;
+mod case;
+mod equal;
 mod identifier;
+mod integer;
 mod is;
+mod matchh;
 mod member_access;
 mod multiple_items;
 mod new_type;
+mod order;
 mod structure;
 mod substitution;
+mod unique;
 
 use super::phrase::Phrase;
 
@@ -32,14 +39,21 @@ macro_rules! phrase {
 pub fn phrases() -> Vec<Phrase> {
     vec![
         any::phrase(),
+        anything::phrase(),
         as_language_item::phrase(),
         builtin::phrase(),
+        case::phrase(),
+        equal::phrase(),
         identifier::phrase(),
+        integer::phrase(),
         is::phrase(),
+        matchh::phrase(),
         member_access::phrase(),
         multiple_items::phrase(),
         new_type::phrase(),
+        order::phrase(),
         structure::phrase(),
         substitution::phrase(),
+        unique::phrase(),
     ]
 }
@@ -7,6 +7,9 @@ use crate::{
     phrase,
 };
 
+/// `ANY Type` defines a parameter. It binds more loosely than substitutions
+/// and member accesses, so `ANY Union(List Nil)` is a parameter whose type is
+/// `Union(List Nil)`, which lets fields refer to the type they are part of.
 pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
     assert_eq!(node.children.len(), 2);
     let r#type = node.children[1].as_item(ctx)?;
@@ -19,6 +22,6 @@ pub fn phrase() -> Phrase {
         "any",
         128,
         Some((create,)),
-        4 => "ANY", 4
+        8 => "ANY", 8
     )
 }
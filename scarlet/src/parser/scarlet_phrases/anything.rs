@@ -0,0 +1,30 @@
+use crate::{
+    definitions::parameter::DParameter,
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        Node,
+    },
+    phrase,
+};
+
+/// `ANYTHING` is shorthand for `ANY ANY Type`. The hidden type parameter is
+/// ordered after every regular parameter so that positional arguments are
+/// never assigned to it ahead of the value it describes.
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 1);
+    let god_type = ctx.env.god_type();
+    let r#type = DParameter::new(129, node.position, god_type);
+    let r#type = ctx.env.new_defined_item(r#type);
+    ctx.env.set_position(r#type, node.position);
+    let definition = DParameter::new(128, node.position, r#type);
+    Ok(ctx.env.new_defined_item(definition))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "anything",
+        128,
+        Some((create,)),
+        4 => "ANYTHING"
+    )
+}
@@ -3,7 +3,7 @@ use crate::{
         builtin::{Builtin, DBuiltin},
         compound_type::DCompoundType,
     },
-    diagnostic::Diagnostic,
+    diagnostic::{Diagnostic, ErrorCode},
     parser::{
         phrase::{CreateContext, CreateResult, Phrase},
         Node,
@@ -19,8 +19,18 @@ pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
         "if_then_else" => Builtin::IfThenElse,
         "Type" => return Ok(ctx.env.new_defined_item(DCompoundType::god_type())),
         "Union" => Builtin::Union,
+        "Nat" => return Ok(ctx.env.new_defined_item(DCompoundType::nat())),
+        "Int" => return Ok(ctx.env.new_defined_item(DCompoundType::int())),
+        "add" => Builtin::Add,
+        "subtract" => Builtin::Subtract,
+        "multiply" => Builtin::Multiply,
+        "divide" => Builtin::Divide,
+        "remainder" => Builtin::Remainder,
+        "is_less_than" => Builtin::IsLessThan,
+        "is_at_most" => Builtin::IsAtMost,
         _ => {
             return Err(Diagnostic::new()
+                .with_code(ErrorCode::UnknownBuiltin)
                 .with_text_error(format!("{} is not the name of any builtin item.", name))
                 .with_source_code_block_error(node.position))
         }
@@ -0,0 +1,12 @@
+use crate::{parser::phrase::Phrase, phrase};
+
+/// `Type => result` is a case of a `MATCH`. Like `IS`, it only means something
+/// inside the phrase which reads it.
+pub fn phrase() -> Phrase {
+    phrase!(
+        "case",
+        128,
+        None,
+        250 => 250, r"=>", 250
+    )
+}
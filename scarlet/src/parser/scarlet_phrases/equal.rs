@@ -0,0 +1,31 @@
+use crate::{
+    definitions::{builtin::DBuiltin, type_of::DTypeOf},
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        Node,
+    },
+    phrase,
+};
+
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 3);
+    let comparee = node.children[0].as_item(ctx)?;
+    let comparand = node.children[2].as_item(ctx)?;
+    let comparee_type = ctx.env.new_defined_item(DTypeOf::new(comparee));
+    let comparand_type = ctx.env.new_defined_item(DTypeOf::new(comparand));
+    ctx.env
+        .set_position(comparee_type, node.children[0].as_node().position);
+    ctx.env
+        .set_position(comparand_type, node.children[2].as_node().position);
+    let definition = DBuiltin::is_exactly(comparee_type, comparand_type, comparee, comparand);
+    Ok(ctx.env.new_defined_item(definition))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "equal",
+        128,
+        Some((create,)),
+        64 => 63, r"=", 63
+    )
+}
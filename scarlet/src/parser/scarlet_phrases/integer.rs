@@ -0,0 +1,25 @@
+use crate::{
+    definitions::integer::DInteger,
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        Node,
+    },
+    phrase,
+};
+
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 1);
+    let value = node.children[0].as_text().parse().unwrap();
+    Ok(ctx.env.new_defined_item(DInteger::new(value)))
+}
+
+/// Digits on their own also match an identifier. Phrases with smaller
+/// priorities are tried first, so they are read as an integer instead.
+pub fn phrase() -> Phrase {
+    phrase!(
+        "integer",
+        128,
+        Some((create,)),
+        4 => r"[0-9]+"
+    )
+}
@@ -0,0 +1,75 @@
+use crate::{
+    definitions::{
+        matchh::{Case, DMatch},
+        parameter::DParameter,
+    },
+    diagnostic::{Diagnostic, ErrorCode, Position},
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        util::collect_comma_list,
+        Node,
+    },
+    phrase,
+};
+
+fn malformed_match(text: String, position: Position) -> Diagnostic {
+    Diagnostic::new()
+        .with_code(ErrorCode::MalformedMatch)
+        .with_text_error(text)
+        .with_source_code_block_error(position)
+}
+
+/// `MATCH(value  Type => result  name IS Type => result)` evaluates to the
+/// result of the first case whose type the value has. A case which names the
+/// value can use it in its result as a value of that type, such as to access
+/// its fields.
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 4);
+    let mut children = collect_comma_list(&node.children[2]).into_iter();
+    let Some(subject) = children.next() else {
+        let text = "Expected a value to match on:".to_owned();
+        return Err(malformed_match(text, node.position));
+    };
+    let subject = subject.as_item(ctx)?;
+    let mut cases = Vec::new();
+    for child in children {
+        if child.phrase != "case" {
+            let text = format!(
+                "Expected a case like `Type => result`, got a {} phrase instead:",
+                child.phrase
+            );
+            return Err(malformed_match(text, child.position));
+        }
+        assert_eq!(child.children.len(), 3);
+        let pattern = child.children[0].as_node();
+        let (label, pattern) = match pattern.as_is() {
+            Some(is) => {
+                let (label, pattern) = is?;
+                (label.to_owned(), pattern)
+            }
+            None => (String::new(), pattern),
+        };
+        let position = child.children[0].as_node().position;
+        let pattern = pattern.as_item(ctx)?;
+        let binding = ctx
+            .env
+            .new_defined_item(DParameter::new(128, position, pattern));
+        ctx.env.set_position(binding, position);
+        let result = child.children[2].as_item(ctx)?;
+        cases.push(Case::new(label, pattern, binding, result));
+    }
+    if cases.is_empty() {
+        let text = "Expected at least one case after the value to match on:".to_owned();
+        return Err(malformed_match(text, node.position));
+    }
+    Ok(ctx.env.new_defined_item(DMatch::new(subject, cases)))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "match",
+        128,
+        Some((create,)),
+        4 => "MATCH", r"\(", 255, r"\)"
+    )
+}
@@ -16,7 +16,11 @@ pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
     for child in collect_comma_list(&node.children[2]) {
         if let Some(is) = child.as_is() {
             let (label, value) = is?;
-            fields.push((label.to_owned(), value.as_item(ctx)?));
+            let value = value.as_item(ctx)?;
+            if let Some(doc) = child.doc() {
+                ctx.env.set_doc(value, doc.to_owned());
+            }
+            fields.push((label.to_owned(), value));
         } else {
             fields.push((String::new(), child.as_item(ctx)?));
         }
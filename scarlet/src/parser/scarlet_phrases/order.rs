@@ -0,0 +1,52 @@
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode},
+    environment::Def0,
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        Node,
+    },
+    phrase,
+};
+
+/// `ANY Type ORDER(n)` sets the major order of a parameter, which decides
+/// which positional argument is assigned to it before its file and location
+/// are considered. Parameters have an order of 128 unless specified otherwise.
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 5);
+    let parameter = node.children[0].as_item(ctx)?;
+    let order = node.children[3].as_node();
+    // Names are read as text too, so that they are reported as invalid orders.
+    let order = if order.phrase == "integer" {
+        order.children[0].as_text()
+    } else {
+        order.as_ident()?
+    };
+    let order: u8 = order.parse().map_err(|_| {
+        Diagnostic::new()
+            .with_code(ErrorCode::InvalidOrder)
+            .with_text_error(format!(
+                "\"{}\" is not a valid order, expected a number from 0 to 255:",
+                order
+            ))
+            .with_source_code_block_error(node.children[3].as_node().position)
+    })?;
+    if let Def0::DParameter(definition) = &ctx.env[parameter] {
+        let definition = definition.with_major_order(order);
+        ctx.env[parameter] = definition.into();
+        Ok(parameter)
+    } else {
+        Err(Diagnostic::new()
+            .with_code(ErrorCode::OrderOnNonParameter)
+            .with_text_error("Only parameters can be given an order:".to_owned())
+            .with_source_code_block_error(node.children[0].as_node().position))
+    }
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "order",
+        128,
+        Some((create,)),
+        236 => 236, r"ORDER", r"\(", 255, r"\)"
+    )
+}
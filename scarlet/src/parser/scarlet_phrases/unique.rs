@@ -0,0 +1,35 @@
+use std::rc::Rc;
+
+use crate::{
+    definitions::{
+        compound_type::{DCompoundType, Type, TypeId},
+        member_access::DUnresolvedMemberAccess,
+    },
+    parser::{
+        phrase::{CreateContext, CreateResult, Phrase},
+        Node,
+    },
+    phrase,
+};
+
+/// `UNIQUE` is shorthand for `NEW_TYPE().new`.
+pub fn create(ctx: &mut CreateContext, node: &Node) -> CreateResult {
+    assert_eq!(node.children.len(), 1);
+    let r#type = DCompoundType::new_single(Rc::new(Type::UserType {
+        type_id: TypeId::UserType(Rc::new(())),
+        fields: vec![],
+    }));
+    let r#type = ctx.env.new_defined_item(r#type);
+    ctx.env.set_position(r#type, node.position);
+    let definition = DUnresolvedMemberAccess::new(r#type, "new".to_owned());
+    Ok(ctx.env.new_defined_item(definition))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "unique",
+        128,
+        Some((create,)),
+        4 => "UNIQUE"
+    )
+}
@@ -0,0 +1,179 @@
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::{json, ser::PrettyFormatter, Serializer};
+
+use super::{
+    parse::{BLOCK_COMMENT_END, BLOCK_COMMENT_START, DOC_COMMENT, LINE_COMMENT},
+    phrase::PhraseComponent,
+    ParseContext,
+};
+
+/// Returns the text a regex matches if it only matches one piece of text.
+fn literal_text(regex: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            text.push(chars.next()?);
+        } else {
+            text.push(c);
+        }
+    }
+    if regex::escape(&text) == regex {
+        Some(text)
+    } else {
+        None
+    }
+}
+
+fn text_regex(component: &PhraseComponent) -> Option<&str> {
+    match component {
+        PhraseComponent::Text(regex) => Some(regex.as_str()),
+        PhraseComponent::Node(..) => None,
+    }
+}
+
+fn is_keyword(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+}
+
+fn scope_of_symbol(text: &str) -> &'static str {
+    match text {
+        "(" | ")" | "[" | "]" | "{" | "}" => "punctuation.section.brackets",
+        "." => "punctuation.accessor",
+        "," => "punctuation.separator",
+        _ => "keyword.operator",
+    }
+}
+
+fn alternatives(texts: &[String]) -> String {
+    texts.iter().map(|text| regex::escape(text)).join("|")
+}
+
+/// Creates a TextMate grammar which highlights the keywords, symbols and
+/// comments that the parser recognizes, formatted as the JSON file the VS
+/// Code extension reads.
+pub fn textmate_grammar(ctx: &ParseContext) -> String {
+    let phrases = &ctx.phrases_sorted_by_priority;
+    let mut keywords = Vec::new();
+    let mut symbols = Vec::new();
+    for phrase in phrases.values() {
+        for component in &phrase.components {
+            let Some(text) = text_regex(component).and_then(literal_text) else { continue };
+            let list = if is_keyword(&text) {
+                &mut keywords
+            } else {
+                &mut symbols
+            };
+            if !list.contains(&text) {
+                list.push(text);
+            }
+        }
+    }
+    keywords.sort();
+    symbols.sort();
+
+    let mut patterns = vec![
+        json!({
+            "name": "comment.block",
+            "begin": regex::escape(BLOCK_COMMENT_START),
+            "end": regex::escape(BLOCK_COMMENT_END),
+            "patterns": [{ "include": "#block-comment" }],
+        }),
+        json!({
+            "name": "comment.line.documentation",
+            "match": format!("{}.*$", regex::escape(DOC_COMMENT)),
+        }),
+        json!({
+            "name": "comment.line",
+            "match": format!("{}.*$", regex::escape(LINE_COMMENT)),
+        }),
+        json!({
+            "name": "keyword.control",
+            "match": format!(r"\b({})\b", alternatives(&keywords)),
+        }),
+    ];
+    let scopes = symbols
+        .iter()
+        .map(|symbol| scope_of_symbol(symbol))
+        .unique();
+    for scope in scopes.collect_vec() {
+        // Longer symbols come first, so that `=>` is not matched as `=`
+        // followed by `>`.
+        let texts: Vec<_> = symbols
+            .iter()
+            .filter(|symbol| scope_of_symbol(symbol) == scope)
+            .sorted_by_key(|symbol| std::cmp::Reverse(symbol.len()))
+            .cloned()
+            .collect();
+        patterns.push(json!({
+            "name": scope,
+            "match": alternatives(&texts),
+        }));
+    }
+
+    // Identifiers are only recognized by the parser if they contain a
+    // lowercase letter or a digit, anything else is an unknown keyword.
+    let identifier = text_regex(&phrases["identifier"].components[0]).unwrap();
+    let integer = text_regex(&phrases["integer"].components[0]).unwrap();
+    let open = text_regex(&phrases["substitution"].components[1]).unwrap();
+    patterns.extend([
+        json!({
+            "name": "invalid.illegal",
+            "match": r"\b[A-Z_]+\b",
+        }),
+        json!({
+            "name": "constant.numeric",
+            "match": format!(r"\b{}\b", integer),
+        }),
+        json!({
+            "name": "entity.name.type",
+            "match": format!(r"\b[A-Z](?:{})?\b", identifier),
+        }),
+        json!({
+            "name": "entity.name.function",
+            "match": format!(r"\b{}(?=[ \r\n\t]*{})", identifier, open),
+        }),
+        json!({
+            "name": "variable",
+            "match": format!(r"\b{}\b", identifier),
+        }),
+    ]);
+
+    let grammar = json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "Scarlet",
+        "patterns": patterns,
+        "repository": {
+            "block-comment": {
+                "begin": regex::escape(BLOCK_COMMENT_START),
+                "end": regex::escape(BLOCK_COMMENT_END),
+                "patterns": [{ "include": "#block-comment" }],
+            },
+        },
+        "scopeName": "source.scarlet",
+    });
+    let mut output = Vec::new();
+    let formatter = PrettyFormatter::with_indent(b"\t");
+    let mut serializer = Serializer::with_formatter(&mut output, formatter);
+    grammar.serialize(&mut serializer).unwrap();
+    String::from_utf8(output).unwrap() + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_in_grammar_is_up_to_date() {
+        let path = "../vscode-syntax/syntaxes/scarlet.tmLanguage.json";
+        let checked_in =
+            std::fs::read_to_string(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap();
+        assert!(
+            checked_in == textmate_grammar(&ParseContext::new()),
+            "The TextMate grammar is out of date. Regenerate it by running `scarlet textmate {}` \
+             in the scarlet folder.",
+            path
+        );
+    }
+}
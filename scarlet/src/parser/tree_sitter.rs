@@ -0,0 +1,242 @@
+use itertools::Itertools;
+
+use super::{
+    node::{Node, NodeChild},
+    parse::{self, BLOCK_COMMENT_END, BLOCK_COMMENT_START, LINE_COMMENT},
+    phrase::{Phrase, PhraseComponent, Precedence},
+    util, ParseContext,
+};
+use crate::file_tree::FileNode;
+
+fn rule_name(phrase: &str) -> String {
+    phrase.replace(' ', "_")
+}
+
+/// Tree-sitter gives higher precedences to rules which bind more tightly,
+/// which is the opposite of how phrases work.
+fn tree_sitter_precedence(precedence: Precedence) -> u8 {
+    255 - precedence
+}
+
+fn string(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+fn text_rule(regex: &str) -> String {
+    let mut text = String::new();
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            text.push(chars.next().unwrap_or(c));
+        } else {
+            text.push(c);
+        }
+    }
+    if regex::escape(&text) == regex {
+        string(&text)
+    } else {
+        format!("/{}/", regex.replace('/', r"\/"))
+    }
+}
+
+fn phrase_rule(phrase: &Phrase) -> String {
+    let components = &phrase.components;
+    let mut parts = Vec::new();
+    for (index, component) in components.iter().enumerate() {
+        let part = match component {
+            PhraseComponent::Text(regex) if phrase.name == "multiple items" => {
+                // The parser inserts a comma between items which are written
+                // next to each other.
+                format!("optional({})", text_rule(regex.as_str()))
+            }
+            PhraseComponent::Text(regex) => text_rule(regex.as_str()),
+            // A node between two pieces of text can be left out, as in `[]`.
+            PhraseComponent::Node(..)
+                if index > 0
+                    && components[index - 1].is_text()
+                    && components.get(index + 1).map(PhraseComponent::is_text) == Some(true) =>
+            {
+                "optional($._expression)".to_owned()
+            }
+            PhraseComponent::Node(..) => "$._expression".to_owned(),
+        };
+        parts.push(part);
+    }
+    if components.iter().any(PhraseComponent::is_node) {
+        let precedence = tree_sitter_precedence(phrase.precedence);
+        // Phrases whose first part cannot be a phrase like themselves, like
+        // `=`, are grouped from the right.
+        let associativity = match &components[0] {
+            PhraseComponent::Node(node) if node.prec < phrase.precedence => "right",
+            _ => "left",
+        };
+        let parts = parts.join(", ");
+        format!("prec.{}({}, seq({}))", associativity, precedence, parts)
+    } else if parts.len() == 1 && parts[0].starts_with('/') && phrase.priority < 255 {
+        // Text which identifiers also match, like digits, is read as the
+        // phrase with the smaller priority. Tree-sitter needs to be told this
+        // with a lexical precedence, which it also gives to higher numbers.
+        format!("token(prec({}, {}))", 255 - phrase.priority, parts[0])
+    } else if parts.len() == 1 {
+        parts.pop().unwrap()
+    } else {
+        format!("seq({})", parts.join(", "))
+    }
+}
+
+/// Escapes a character for use in a JavaScript regex, inside or outside of
+/// square brackets.
+fn regex_char(c: char) -> String {
+    if r"\^$.|?*+()[]{}/-".contains(c) {
+        format!("\\{}", c)
+    } else {
+        c.to_string()
+    }
+}
+
+fn comment_rules() -> Vec<(String, String)> {
+    let line_start = string(LINE_COMMENT);
+    let block_start = string(BLOCK_COMMENT_START);
+    let after_start = BLOCK_COMMENT_START[LINE_COMMENT.len()..].chars().next();
+    let not_block = regex_char(after_start.unwrap());
+    let line_comment = format!(
+        "token(choice({}, seq({}, /[^{}\\n][^\\n]*/)))",
+        line_start, line_start, not_block
+    );
+    // A regex can't count how deeply comments are nested, so nested block
+    // comments end at the first end marker. This assumes the end marker is
+    // two characters long, like `=#`.
+    let mut end = BLOCK_COMMENT_END.chars().map(regex_char);
+    let (first, last) = (end.next().unwrap(), end.next().unwrap());
+    let body = format!(
+        "[^{first}]*{first}+([^{last}{first}][^{first}]*{first}+)*{last}",
+        first = first,
+        last = last,
+    );
+    let block_comment = format!("token(seq({}, /{}/))", block_start, body);
+    vec![
+        ("line_comment".to_owned(), line_comment),
+        ("block_comment".to_owned(), block_comment),
+    ]
+}
+
+/// Creates a `grammar.js` file which tree-sitter can use to parse the same
+/// phrases as the parser.
+pub fn tree_sitter_grammar(ctx: &ParseContext) -> String {
+    let phrases = ctx
+        .phrases_sorted_by_priority
+        .values()
+        .sorted_by_key(|phrase| phrase.name)
+        .collect_vec();
+    let mut rules = vec![
+        ("source_file".to_owned(), "optional($._expression)".to_owned()),
+        (
+            "_expression".to_owned(),
+            format!(
+                "choice(\n      {},\n    )",
+                phrases
+                    .iter()
+                    .map(|phrase| format!("$.{}", rule_name(phrase.name)))
+                    .join(",\n      ")
+            ),
+        ),
+    ];
+    for phrase in &phrases {
+        rules.push((rule_name(phrase.name), phrase_rule(phrase)));
+    }
+    rules.extend(comment_rules());
+
+    let mut grammar = String::new();
+    grammar.push_str("// Generated by `scarlet tree-sitter` from the phrases the parser\n");
+    grammar.push_str("// recognizes.\n");
+    grammar.push_str("module.exports = grammar({\n");
+    grammar.push_str("  name: 'scarlet',\n\n");
+    grammar.push_str("  extras: $ => [/[ \\r\\n\\t]/, $.line_comment, $.block_comment],\n\n");
+    grammar.push_str("  word: $ => $.identifier,\n\n");
+    grammar.push_str("  rules: {\n");
+    for (name, rule) in rules {
+        grammar.push_str(&format!("    {}: $ => {},\n\n", name, rule));
+    }
+    grammar.truncate(grammar.len() - 1);
+    grammar.push_str("  },\n});\n");
+    grammar
+}
+
+fn s_expression(node: &Node) -> String {
+    let children = node
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            NodeChild::Node(child) => Some(s_expression(child)),
+            _ => None,
+        })
+        .collect_vec();
+    if children.is_empty() {
+        format!("({})", rule_name(node.phrase))
+    } else {
+        format!("({} {})", rule_name(node.phrase), children.join(" "))
+    }
+}
+
+/// Creates a tree-sitter test corpus from the files in a tree, with one test
+/// for each item at the top of each file. The expected syntax trees are the
+/// ones the parser produces.
+pub fn tree_sitter_corpus(name: &str, tree: &FileNode, ctx: &ParseContext) -> String {
+    let mut corpus = String::new();
+    for (path, content) in tree.files() {
+        let Ok(Some(root)) = parse::parse(content, ctx, 1) else { continue };
+        for item in util::collect_comma_list(&NodeChild::Node(root)) {
+            let code = &content[item.position.range()];
+            // Comments appear in the trees tree-sitter produces, but not in
+            // the ones the parser produces.
+            if code.contains(LINE_COMMENT) {
+                continue;
+            }
+            let title = match item.as_is() {
+                Some(Ok((label, _))) => format!("{}{}.sr: {}", name, path, label),
+                _ => format!("{}{}.sr: {}", name, path, code.lines().next().unwrap()),
+            };
+            let rule = "=".repeat(title.len());
+            corpus.push_str(&format!("{}\n{}\n{}\n\n", rule, title, rule));
+            corpus.push_str(&format!("{}\n\n---\n\n", code));
+            corpus.push_str(&format!("(source_file {})\n\n", s_expression(item)));
+        }
+    }
+    corpus.truncate(corpus.trim_end().len() + 1);
+    corpus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_tree;
+
+    fn assert_up_to_date(path: &str, generated: &str) {
+        let checked_in =
+            std::fs::read_to_string(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap();
+        assert!(
+            checked_in == generated,
+            "{} is out of date. Regenerate it by running `scarlet tree-sitter \
+             ../tree-sitter-scarlet mini/mini moderate/moderate full/full` in the scarlet folder.",
+            path
+        );
+    }
+
+    #[test]
+    fn checked_in_grammar_is_up_to_date() {
+        let grammar = tree_sitter_grammar(&ParseContext::new());
+        assert_up_to_date("../tree-sitter-scarlet/grammar.js", &grammar);
+    }
+
+    #[test]
+    fn checked_in_corpora_are_up_to_date() {
+        let ctx = ParseContext::new();
+        for name in ["mini", "moderate", "full"] {
+            let root = format!("{}/{}/{}", env!("CARGO_MANIFEST_DIR"), name, name);
+            let tree = file_tree::read_root(root).unwrap();
+            let corpus = tree_sitter_corpus(name, &tree, &ctx);
+            let path = format!("../tree-sitter-scarlet/test/corpus/{}.txt", name);
+            assert_up_to_date(&path, &corpus);
+        }
+    }
+}
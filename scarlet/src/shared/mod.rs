@@ -28,6 +28,16 @@ pub enum TripleBool {
     Unknown,
 }
 
+impl From<bool> for TripleBool {
+    fn from(value: bool) -> Self {
+        if value {
+            TripleBool::True
+        } else {
+            TripleBool::False
+        }
+    }
+}
+
 impl TripleBool {
     pub fn and(over: Vec<TripleBool>) -> TripleBool {
         let mut known_true = true;
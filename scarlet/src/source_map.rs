@@ -0,0 +1,187 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+use crate::{diagnostic::Position, file_tree::FileNode};
+
+/// A file which positions can refer to.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    /// The path of the file relative to the root of the program, without an
+    /// extension, such as `/folder/file`. This is empty for the root file.
+    pub relative_path: String,
+    /// The path used to refer to the file when showing it to the user.
+    pub path: String,
+    pub content: String,
+    /// The byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(relative_path: String, path: String, content: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            relative_path,
+            path,
+            content,
+            line_starts,
+        }
+    }
+
+    /// Returns the byte range of a line, counting from one, without the line
+    /// break at its end.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.line_starts[line - 1];
+        let end = match self.line_starts.get(line) {
+            Some(&next) => next - 1,
+            None => self.content.len(),
+        };
+        let end = if self.content[start..end].ends_with('\r') {
+            end - 1
+        } else {
+            end
+        };
+        start..end
+    }
+
+    /// Returns the line and column of a byte offset into the file, both
+    /// counting from one. Columns count characters, not bytes.
+    pub fn line_and_column(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.content.len());
+        while !self.content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.content[line_start..offset].chars().count() + 1;
+        (line, column)
+    }
+}
+
+/// Where a position is, in terms a user can find in their editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub path: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl<'a> Display for Location<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+/// The paths and contents of every file in a program, for turning positions
+/// back into something readable.
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Creates a source map for the files read from the given path.
+    pub fn new(root: &str, tree: &FileNode) -> Self {
+        let files = tree
+            .files()
+            .into_iter()
+            .map(|(relative_path, content)| {
+                let path = format!("{}{}.sr", root, relative_path);
+                SourceFile::new(relative_path, path, content.to_owned())
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Returns the file a position with the given file index is in. Index
+    /// zero is used for code which was generated by the compiler, which is
+    /// not in any file.
+    pub fn file(&self, file_index: usize) -> Option<&SourceFile> {
+        self.files.get(file_index.checked_sub(1)?)
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// Returns the text a position covers.
+    pub fn text(&self, position: Position) -> Option<&str> {
+        self.file(position.file_index())?
+            .content
+            .get(position.range())
+    }
+
+    /// Returns the file, line and column at which a position starts.
+    pub fn location(&self, position: Position) -> Option<Location<'_>> {
+        let file = self.file(position.file_index())?;
+        let (line, column) = file.line_and_column(position.range().start);
+        Some(Location {
+            path: &file.path,
+            line,
+            column,
+        })
+    }
+
+    /// Describes where a position is for debug output, falling back to its
+    /// raw offsets if it is not in a file.
+    pub fn describe(&self, position: Position) -> String {
+        match self.location(position) {
+            Some(location) => location.to_string(),
+            None => format!("[generated] {:?}", position.range()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_map() -> SourceMap {
+        let child = FileNode {
+            self_content: "inner IS ANY Type".to_owned(),
+            children: Vec::new(),
+        };
+        let root = FileNode {
+            self_content: "héllo IS UNIQUE\r\nwörld IS ∀x\nend".to_owned(),
+            children: vec![("sub".to_owned(), child)],
+        };
+        SourceMap::new("program", &root)
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let sources = source_map();
+        let file = sources.file(1).unwrap();
+        let content = &file.content;
+        assert_eq!(file.line_and_column(content.find("IS").unwrap()), (1, 7));
+        assert_eq!(file.line_and_column(content.find('x').unwrap()), (2, 11));
+        assert_eq!(file.line_and_column(content.find("end").unwrap()), (3, 1));
+        // Offsets inside a character are where that character starts.
+        let inside = content.find('∀').unwrap() + 1;
+        assert_eq!(file.line_and_column(inside), (2, 10));
+        assert_eq!(file.line_and_column(content.len() + 10), (3, 4));
+        assert_eq!(&content[file.line_range(1)], "héllo IS UNIQUE");
+        assert_eq!(&content[file.line_range(2)], "wörld IS ∀x");
+    }
+
+    #[test]
+    fn locations_name_the_file() {
+        let sources = source_map();
+        let inner = Position::new(2, 9..12);
+        assert_eq!(sources.text(inner), Some("ANY"));
+        assert_eq!(sources.describe(inner), "program/sub.sr:1:10");
+        let start = sources.file(1).unwrap().content.find("wörld").unwrap();
+        let location = sources.location(Position::new(1, start..start + 1));
+        let expected = Location {
+            path: "program.sr",
+            line: 2,
+            column: 1,
+        };
+        assert_eq!(location, Some(expected));
+        assert_eq!(sources.file(2).unwrap().relative_path, "/sub");
+        assert_eq!(sources.describe(Position::new(0, 3..5)), "[generated] 3..5");
+    }
+}
@@ -0,0 +1,71 @@
+// Generated by `scarlet tree-sitter` from the phrases the parser
+// recognizes.
+module.exports = grammar({
+  name: 'scarlet',
+
+  extras: $ => [/[ \r\n\t]/, $.line_comment, $.block_comment],
+
+  word: $ => $.identifier,
+
+  rules: {
+    source_file: $ => optional($._expression),
+
+    _expression: $ => choice(
+      $.any,
+      $.anything,
+      $.as_language_item,
+      $.builtin,
+      $.case,
+      $.equal,
+      $.identifier,
+      $.integer,
+      $.is,
+      $.match,
+      $.member_access,
+      $.multiple_items,
+      $.new_type,
+      $.order,
+      $.structure,
+      $.substitution,
+      $.unique,
+    ),
+
+    any: $ => prec.left(247, seq("ANY", $._expression)),
+
+    anything: $ => "ANYTHING",
+
+    as_language_item: $ => prec.left(19, seq($._expression, "AS_LANGUAGE_ITEM", "(", optional($._expression), ")")),
+
+    builtin: $ => prec.left(251, seq("BUILTIN", "(", optional($._expression), ")")),
+
+    case: $ => prec.left(5, seq($._expression, "=>", $._expression)),
+
+    equal: $ => prec.right(191, seq($._expression, "=", $._expression)),
+
+    identifier: $ => /[a-zA-Z0-9_]+/,
+
+    integer: $ => token(prec(127, /[0-9]+/)),
+
+    is: $ => prec.left(7, seq($._expression, "IS", $._expression)),
+
+    match: $ => prec.left(251, seq("MATCH", "(", optional($._expression), ")")),
+
+    member_access: $ => prec.left(251, seq($._expression, ".", $._expression)),
+
+    multiple_items: $ => prec.left(3, seq($._expression, optional(","), $._expression)),
+
+    new_type: $ => prec.left(251, seq("NEW_TYPE", "(", optional($._expression), ")")),
+
+    order: $ => prec.left(19, seq($._expression, "ORDER", "(", optional($._expression), ")")),
+
+    structure: $ => prec.left(251, seq("[", optional($._expression), "]")),
+
+    substitution: $ => prec.left(251, seq($._expression, "(", optional($._expression), ")")),
+
+    unique: $ => "UNIQUE",
+
+    line_comment: $ => token(choice("#", seq("#", /[^=\n][^\n]*/))),
+
+    block_comment: $ => token(seq("#=", /[^=]*=+([^#=][^=]*=+)*#/)),
+  },
+});